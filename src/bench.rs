@@ -0,0 +1,96 @@
+use astro_and_robots::state::{GenerationOptions, State};
+use color_eyre::Result;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+struct Sample {
+    generation: Duration,
+    solve: Duration,
+    solution_moves: usize,
+    attempts: usize,
+}
+
+/// generates `count` boards of `size`x`size` over incrementing seeds,
+/// and reports generation time, solve time and optimal solution lengths.
+pub fn run(size: usize, count: usize, options: GenerationOptions) -> Result<()> {
+    let mut samples = Vec::with_capacity(count);
+    let mut failures = 0;
+
+    for seed in 0..count as u64 {
+        let start = Instant::now();
+        let options = GenerationOptions {
+            seed: Some(seed),
+            ..options.clone()
+        };
+        let Ok(generated) = State::generate(size, size, &options) else {
+            failures += 1;
+            continue;
+        };
+        let generation = start.elapsed();
+
+        let start = Instant::now();
+        let solution = generated
+            .state
+            .solve_from_here()
+            .expect("generated states are solvable");
+        let solve = start.elapsed();
+
+        samples.push(Sample {
+            generation,
+            solve,
+            solution_moves: solution.len() - 1,
+            attempts: generated.attempts,
+        });
+    }
+
+    println!(
+        "{size}x{size}: {} boards generated, {failures} failed",
+        samples.len()
+    );
+    if samples.is_empty() {
+        return Ok(());
+    }
+
+    let generation_times = samples.iter().map(|sample| sample.generation).collect_vec();
+    let solve_times = samples.iter().map(|sample| sample.solve).collect_vec();
+    print_timings("generation", generation_times);
+    print_timings("solve", solve_times);
+
+    let attempts = samples.iter().map(|sample| sample.attempts).collect_vec();
+    let mean_attempts = attempts.iter().sum::<usize>() / attempts.len();
+    let max_attempts = attempts.iter().max().expect("samples is not empty");
+    println!("  attempts: mean {mean_attempts}, max {max_attempts}");
+
+    let lengths = samples.iter().map(|sample| sample.solution_moves);
+    println!();
+    println!("optimal solution lengths (moves):");
+    print_histogram(lengths);
+
+    Ok(())
+}
+
+fn print_timings(label: &str, mut times: Vec<Duration>) {
+    times.sort();
+    let mean = times.iter().sum::<Duration>() / times.len() as u32;
+    let median = times[times.len() / 2];
+    println!("{label:>10}: mean {mean:?}, median {median:?}");
+}
+
+pub fn print_histogram(values: impl IntoIterator<Item = usize>) {
+    const MAX_BAR_WIDTH: usize = 40;
+
+    let counts = values
+        .into_iter()
+        .counts()
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    let max_count = counts.values().copied().max().unwrap_or(0);
+
+    for (value, count) in counts {
+        //scale bars so the most common value fills the full width
+        let width = (count * MAX_BAR_WIDTH).div_ceil(max_count);
+        let bar = "#".repeat(width);
+        println!("{value:>4} | {bar} {count}");
+    }
+}
@@ -0,0 +1,60 @@
+use astro_and_robots::game::Game;
+use color_eyre::eyre::ensure;
+use color_eyre::Result;
+use std::io::{stdin, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use termion::input::TermRead;
+
+//how long each solved board stays up before the next one replaces it
+const PAUSE: Duration = Duration::from_millis(1500);
+
+/// plays randomly-generated boards one after the other by autoplaying their walkthroughs,
+/// until any key is pressed. a board that can't be generated is tried again with the next seed.
+/// expects the terminal to already be in raw mode.
+pub fn run(mut game: Game, stdout: &mut impl Write) -> Result<()> {
+    ensure!(
+        game.can_generate_new_board(),
+        "the demo can only play randomly-generated boards"
+    );
+
+    //keys are read on their own thread, so that the boards can play while waiting for one
+    let (key_sender, keys) = mpsc::channel();
+    thread::spawn(move || {
+        for key in stdin().keys() {
+            if key_sender.send(key).is_err() {
+                break;
+            }
+        }
+    });
+
+    autoplay(&mut game);
+    game.draw(stdout)?;
+    loop {
+        let wait = if game.is_autoplaying() {
+            game.autoplay_interval()
+        } else {
+            PAUSE
+        };
+        match keys.recv_timeout(wait) {
+            Ok(_) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) if game.is_autoplaying() => game.autoplay_step(),
+            Err(RecvTimeoutError::Timeout) => match game.new_board() {
+                Ok(()) => autoplay(&mut game),
+                Err(err) => game.set_status(format!("no new board: {err}, trying again")),
+            },
+        }
+        game.draw(stdout)?;
+    }
+
+    //leave the shell prompt below the last drawn screen
+    writeln!(stdout, "\r")?;
+    Ok(())
+}
+
+/// starts autoplaying the walkthrough of a board that was just loaded
+fn autoplay(game: &mut Game) {
+    game.toggle_mode();
+    game.toggle_autoplay();
+}
@@ -0,0 +1,42 @@
+use crate::state::Pos;
+use std::time::Duration;
+use thiserror::Error;
+
+/// why a board couldn't be built, parsed or solved, for callers that need to tell the cases apart.
+/// the binary reports these like any other error.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GameError {
+    //the state is the debug line of the board it couldn't be solved from
+    #[error("game cannot be solved from {state}")]
+    Unsolvable { state: String },
+    #[error("gave up solving after {:.1}s and {explored} positions", .elapsed.as_secs_f64())]
+    GaveUp { explored: usize, elapsed: Duration },
+    #[error("no player")]
+    NoPlayer,
+    #[error("more than one player")]
+    MultiplePlayers,
+    #[error("no goal")]
+    NoGoal,
+    #[error("empty grid")]
+    EmptyGrid,
+    #[error("unknown tile `{0}`")]
+    UnknownTile(char),
+    //1-based, as editors count them
+    #[error("line {line}, column {col}: {reason}")]
+    ParseError {
+        line: usize,
+        col: usize,
+        reason: String,
+    },
+    //for the JSON level format, whose errors have no line and column to point at
+    #[error("{0}")]
+    InvalidLevel(String),
+    #[error("{piece} at {pos} is out of bounds")]
+    OutOfBounds { piece: &'static str, pos: Pos },
+    #[error("{first} and {second} overlap at {pos}")]
+    Overlap {
+        first: &'static str,
+        second: &'static str,
+        pos: Pos,
+    },
+}
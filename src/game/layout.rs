@@ -0,0 +1,103 @@
+use termion::cursor;
+
+/// where on the terminal a block of content is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    //1-based (col, row) of the content's top-left corner
+    origin: (u16, u16),
+    available: (usize, usize),
+}
+
+impl Layout {
+    /// starts `content` (cols, rows) in the middle of the terminal.
+    ///
+    /// if the terminal size is unknown (e.g. output is not a tty), content starts at the top-left.
+    /// if the content doesn't fit, it starts near the top-left, leaving room for the clip indicators.
+    pub fn new(terminal_size: Option<(u16, u16)>, content: (usize, usize)) -> Self {
+        let Some((term_cols, term_rows)) =
+            terminal_size.filter(|&(cols, rows)| cols > 0 && rows > 0)
+        else {
+            return Layout {
+                origin: (1, 1),
+                available: (usize::MAX, usize::MAX),
+            };
+        };
+
+        let (centered_cols, centered_rows) = Layout::centered_capacity((term_cols, term_rows));
+        let (content_cols, content_rows) = content;
+        let fits = content_cols <= centered_cols && content_rows <= centered_rows;
+
+        let origin = if fits {
+            middle((term_cols, term_rows))
+        } else {
+            (2.min(term_cols), 2.min(term_rows))
+        };
+
+        //one cell is left on the right and bottom for the clip indicators
+        let available = (
+            usize::from(term_cols - origin.0),
+            usize::from(term_rows - origin.1),
+        );
+
+        Layout { origin, available }
+    }
+
+    /// the (cols, rows) of cells that content can be drawn in when it starts in the middle of the terminal
+    pub fn centered_capacity(terminal_size: (u16, u16)) -> (usize, usize) {
+        let (term_cols, term_rows) = terminal_size;
+        let (mid_cols, mid_rows) = middle(terminal_size);
+
+        //one cell is left on the right and bottom for the clip indicators
+        (
+            usize::from(term_cols.saturating_sub(mid_cols)),
+            usize::from(term_rows.saturating_sub(mid_rows)),
+        )
+    }
+
+    /// the part of this layout that starts `cols` and `rows` cells further in,
+    /// leaving room before it for things like labels
+    pub fn inset(&self, cols: u16, rows: u16) -> Layout {
+        let (origin_col, origin_row) = self.origin;
+        let (available_cols, available_rows) = self.available;
+
+        Layout {
+            origin: (
+                origin_col.saturating_add(cols),
+                origin_row.saturating_add(rows),
+            ),
+            available: (
+                available_cols.saturating_sub(usize::from(cols)),
+                available_rows.saturating_sub(usize::from(rows)),
+            ),
+        }
+    }
+
+    /// the same layout, `cols` cells further right
+    pub fn shifted(&self, cols: u16) -> Layout {
+        Layout {
+            origin: (self.origin.0.saturating_add(cols), self.origin.1),
+            ..*self
+        }
+    }
+
+    /// the (cols, rows) of cells that content can be drawn in
+    pub fn available(&self) -> (usize, usize) {
+        self.available
+    }
+
+    pub fn origin(&self) -> (u16, u16) {
+        self.origin
+    }
+
+    pub fn goto(&self, col_offset: u16, row_offset: u16) -> cursor::Goto {
+        let (col, row) = self.origin;
+        cursor::Goto(
+            col.saturating_add(col_offset),
+            row.saturating_add(row_offset),
+        )
+    }
+}
+
+fn middle((term_cols, term_rows): (u16, u16)) -> (u16, u16) {
+    ((term_cols / 2).max(1), (term_rows / 2).max(1))
+}
@@ -1,20 +1,312 @@
+mod layout;
 pub mod solver;
+mod viewport;
 
-use crate::state::{Direction, MovementAttempt, Pos, Selection, State, Tile};
-use color_eyre::eyre::eyre;
+use crate::error::GameError;
+use crate::state::{
+    CollisionRule, Direction, GenerationOptions, GoalOccupant, Movement, MovementAttempt, Pos,
+    PosChange, Selection, State, StopRule, Tile, WinCondition,
+};
+use color_eyre::eyre::{ensure, eyre, Context};
 use color_eyre::{Report, Result};
 use itertools::Itertools;
-use std::fmt::Display;
+use layout::Layout;
+use lru::LruCache;
+use solver::{MoveCosts, SolveOutcome, SolverKind};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
 use std::io::Write;
-use std::iter;
-use termion::{clear, color, cursor, terminal_size};
+use std::num::NonZeroUsize;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{fs, iter, mem};
+use termion::event::Key;
+use termion::{clear, color, cursor, style, terminal_size};
+use viewport::Viewport;
+
+//blockers can't be selected or moved, so they're drawn faded
+const BLOCKER_COLOR: color::LightBlack = color::LightBlack;
+const PREVIEW_COLOR: color::LightBlack = color::LightBlack;
+//the arrows `toggle_trace` draws where the selected piece would stop
+const TRACE_COLOR: color::LightCyan = color::LightCyan;
+//faint, so that the tile the last move left reads as a hint rather than a piece
+const VACATED_GLYPH: char = '·';
+const VACATED_COLOR: color::LightBlack = color::LightBlack;
+//where the astro could get to on its own, see `toggle_astro_reach`
+const ASTRO_REACH_GLYPH: char = '+';
+//the walkthrough draws this over the empty tiles a piece slid through on its last move
+const TRAIL_GLYPH: char = '~';
+const TRAIL_COLOR: color::LightRed = color::LightRed;
+//revealing the solution marks where each robot ends up, since robots only ever stop once per move
+const REVEALED_ROBOT_GLYPH: char = 'o';
+//the walkthrough can number where the astro was at each earlier step
+const STEP_NUMBER_COLOR: color::Cyan = color::Cyan;
+//the move space overlay shades the tiles pieces can stop on, the astro's taking precedence
+const ASTRO_REACH_COLOR: color::Green = color::Green;
+const ROBOT_REACH_COLOR: color::Blue = color::Blue;
+//drawn instead of the astro once it reaches the goal, when colors are not enough
+const ASTRO_AT_GOAL_GLYPH: char = '@';
+//drawn instead of a robot resting on a goal, in accessible mode with goal emphasis
+const ROBOT_AT_GOAL_GLYPH: char = '&';
+const GOAL_COLOR: color::Yellow = color::Yellow;
+//drawn instead of a piece resting on a goal when the goal takes priority, in the goal color
+const ASTRO_ON_GOAL_GLYPH: char = 'Â';
+const PARTNER_ON_GOAL_GLYPH: char = 'â';
+const ROBOT_ON_GOAL_GLYPH: char = 'Ř';
+
+//cleared at startup for terminals that shouldn't get colors, see `set_colors`
+static COLORS: AtomicBool = AtomicBool::new(true);
+//the robot that has to reach the goal, when it's a particular one
+const TARGET_COLOR: color::Magenta = color::Magenta;
+//drawn over the tiles that `--fog` hides
+const FOG_GLYPH: char = '?';
+const FOG_COLOR: color::LightBlack = color::LightBlack;
+//faint, so that the lines don't compete with the pieces
+const GRID_LINE_COLOR: color::LightBlack = color::LightBlack;
+//the columns between the two solutions in the review
+const REVIEW_GAP: usize = 4;
+//the walkthrough never autoplays faster than this, so that slow terminals can keep up
+pub const MAX_SPEED: u32 = 30;
+//boards larger than this in either direction are scrolled, so the terminal only has to show this much of them
+const MIN_VISIBLE_TILES: usize = 4;
+//how many positions the optimal distances are remembered for
+const DISTANCE_CACHE_SIZE: NonZeroUsize = NonZeroUsize::new(1024).unwrap();
+//the assist shades the tiles of the next optimal move faintly, so that it isn't mistaken for the selection
+//a dark gray, the same as `AnsiValue::grayscale(6)` which can't be called in a const
+const ASSIST_COLOR: color::AnsiValue = color::AnsiValue(238);
+//the assist re-solves after every move, which is too slow on boards with more tiles than this
+const ASSIST_MAX_TILES: usize = 64;
+//boards are solved before they can be played, so a pathological one gives up rather than hanging
+const SOLVE_MAX_NODES: usize = 1_000_000;
+const SOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+//how long a solved board stays up before `--auto-new` replaces it
+const AUTO_NEW_PAUSE: Duration = Duration::from_millis(1500);
 
 #[derive(Debug)]
 pub struct Game {
     moves: Vec<State>,
+    undone: Vec<Undone>,
     selected: Selection,
     mode: Mode,
     walkthrough: SolutionWalkthrough,
+    sequence: Option<BoardSequence>,
+    //when set, the walkthrough shows the cheapest solution instead of the shortest
+    move_costs: Option<MoveCosts>,
+    //what the walkthrough's solution is optimal in, which can be switched while watching it
+    objective: SolverKind,
+    show_preview: bool,
+    //an arrow on each tile the selected piece would stop on, pointing the way it would slide there
+    show_trace: bool,
+    //marks the tile the last piece moved off, until the next move, undo or restart
+    show_vacated: bool,
+    vacated: Option<Pos>,
+    //marks every tile the astro could come to rest on without moving a robot
+    show_astro_reach: bool,
+    show_coordinates: bool,
+    show_move_space: bool,
+    //shades where the next optimal move starts and ends, as a learning aid
+    show_assist: bool,
+    //names the next optimal move and only lets moves that stay on an optimal line be made
+    guided: bool,
+    //the optimal solution from the state it starts with, drawn over the board while that's the current state
+    revealed: Option<Vec<State>>,
+    goal_style: GoalStyle,
+    render_priority: RenderPriority,
+    labels: Labels,
+    move_feedback: MoveFeedback,
+    //whether selecting past the last piece goes back to the first, and the other way around
+    wrap_selection: bool,
+    //whether the last move was rejected, so that the next draw gives `move_feedback`
+    rejected_move: bool,
+    //told about moves, undos and wins, for frontends that need to react to them
+    event_handler: Option<EventHandler>,
+    //draws a space between tiles, so that columns are easier to tell apart
+    spacing: bool,
+    //drawn between the tiles, both within rows and between them
+    grid_lines: Option<GridLines>,
+    //when set, only the tiles within this many steps of the astro are shown while playing
+    fog: Option<usize>,
+    //the board has to be solved within this, or the game ends out of time
+    time_limit: Option<TimeLimit>,
+    //re-solves on every move, which can be slow on large boards
+    show_remaining_optimal: bool,
+    //marks selected and moved pieces with symbols as well as colors
+    accessible: bool,
+    //re-solves on every move, like `show_remaining_optimal`
+    warn_unsolvable: bool,
+    //how the current board was generated, if it was, so that more can be generated like it
+    generation: Option<GenerationOptions>,
+    //the sizes `resize_board` can switch generated boards between
+    board_sizes: Option<RangeInclusive<usize>>,
+    //when play on the current board started, reset by restarting
+    started: Instant,
+    solve_time: Option<Duration>,
+    //the fewest moves the current board can be solved in, even when scoring by cost
+    par: usize,
+    undos_used: usize,
+    //every move is final: history can't be undone, redone or restarted
+    hardcore: bool,
+    //when set, only this many moves can be undone, and older states are dropped from `moves`
+    undo_limit: Option<usize>,
+    //how many states were dropped from between the initial state and the rest of `moves`.
+    //the initial state is always kept, so that restarting still works
+    dropped_moves: usize,
+    //the number of moves made when the player marked a position to come back to.
+    //forgotten once that position is no longer in the history
+    bookmark: Option<usize>,
+    //the keys listed once the board is solved, which other frontends may not have
+    show_game_over_menu: bool,
+    //when set, each robot can only be moved this many times, and the walkthrough keeps to that
+    robot_moves: Option<usize>,
+    show_step_numbers: bool,
+    //whether the walkthrough steps forward on its own, `speed` steps per second
+    autoplay: bool,
+    speed: u32,
+    //while practicing, pieces step one tile at a time and the real history is set aside here
+    practice: Option<(Vec<State>, Vec<Undone>)>,
+    //a read-only cursor for looking at tiles, independent of the selected piece
+    inspector: Option<Pos>,
+    //the optimal number of moves left from positions solved during play, or `None` if unsolvable.
+    //positions never change their distance, so nothing is ever invalidated
+    distances: RefCell<LruCache<State, Option<usize>>>,
+    //the position the next optimal move leads to, from positions solved during play
+    next_optimal: RefCell<LruCache<State, State>>,
+    //a one-line message shown below the board until the next action
+    status: Option<String>,
+    //the controls are drawn over the board until the next key
+    show_help: bool,
+    //the direction of a board change that has to be asked for again, since it would lose progress
+    pending_browse: Option<i32>,
+    //the step both solutions are shown at in the review
+    review_step: usize,
+    //with `--auto-new`, the boards solved before the current one
+    session: Option<Session>,
+    //whether a key kept the current solved board up, rather than moving on to a new one
+    auto_new_cancelled: bool,
+}
+
+/// a move taken back, along with what was selected and the mode when it was,
+/// so that redoing it goes back to exactly where the player was
+#[derive(Debug)]
+struct Undone {
+    state: State,
+    selected: Selection,
+    mode: Mode,
+}
+
+/// the running tally of the boards solved one after the other with `--auto-new`
+#[derive(Debug)]
+struct Session {
+    solved: usize,
+    moves: usize,
+    started: Instant,
+}
+
+#[derive(Debug)]
+struct BoardSequence {
+    //shown alongside the progress through `boards`
+    name: String,
+    boards: Vec<State>,
+    current: usize,
+    //whether `[` and `]` can skip between boards, rather than only moving on once one is solved
+    browsable: bool,
+}
+
+/// how much the goals stand out from the rest of the board
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GoalStyle {
+    #[default]
+    Plain,
+    //colored and bold, with the pieces resting on it drawn over the goal color
+    Bold,
+    //like `Bold`, but blinking. falls back to `Bold` in accessible mode
+    Blink,
+}
+
+/// what's drawn when a piece is resting on a goal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RenderPriority {
+    //the piece draws over the goal, as `State::tile_at` has it
+    #[default]
+    Pieces,
+    //the goal stays visible, with the piece on it marked by a combined glyph
+    Goal,
+}
+
+/// the characters the lines between tiles are drawn with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GridLines {
+    #[default]
+    Box,
+    //for terminals without box-drawing characters
+    Ascii,
+}
+
+impl GridLines {
+    /// the (vertical, horizontal, crossing) characters
+    fn glyphs(self) -> (char, char, char) {
+        match self {
+            GridLines::Box => ('│', '─', '┼'),
+            GridLines::Ascii => ('|', '-', '+'),
+        }
+    }
+}
+
+/// what happens when the selected piece can't move the way it was told to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MoveFeedback {
+    #[default]
+    Silent,
+    //rings the terminal bell
+    Bell,
+    //briefly draws the selected piece inverted
+    Flash,
+}
+
+/// what the pieces are called in text, so that reskinned variants can rename them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Labels {
+    pub astro: String,
+    pub robot: String,
+    //the plural of `robot`, which isn't always just an added s
+    pub robots: String,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Labels {
+            astro: "astro".to_string(),
+            robot: "robot".to_string(),
+            robots: "robots".to_string(),
+        }
+    }
+}
+
+impl Labels {
+    /// robots are numbered from 1, in the order `select_next_character` goes through them
+    pub fn name(&self, selection: Selection) -> String {
+        match selection {
+            Selection::Astro => self.astro.clone(),
+            Selection::Partner => format!("second {}", self.astro),
+            Selection::Robot(n) => format!("{} {}", self.robot, n + 1),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        format!("{} and {}", self.astro, self.robots).to_uppercase()
+    }
+}
+
+/// a part of the terminal to draw a game in, such as one side of a split screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    //(cols, rows)
+    pub size: (u16, u16),
+    //how far the area is from the left edge of the terminal
+    pub col_offset: u16,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -22,258 +314,3459 @@ pub enum Mode {
     Playable,
     Walkthrough,
     GameOver,
+    //the moves that solved the board alongside the walkthrough, once it's solved
+    Review,
+    //the time limit ran out before the board was solved
+    TimeUp,
+}
+
+/// how long a board may take to solve, as `Game::with_time_limit` sets it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeLimit {
+    Fixed(Duration),
+    /// this much for each move of the optimal solution, so that longer boards get longer
+    PerParMove(Duration),
 }
 
 impl Game {
     pub fn new(initial_state: State) -> Result<Self> {
-        let solution = initial_state
-            .solve_from_here()
-            .ok_or_else(|| eyre!("game cannot be solved from this state"))?;
+        let solution = solve(&initial_state, SolverKind::Shortest)?;
+        let par = solution.len() - 1;
 
         let game = Game {
             moves: vec![initial_state],
+            undone: Vec::new(),
             selected: Selection::Astro,
             mode: Mode::Playable,
             walkthrough: SolutionWalkthrough::new(solution),
+            sequence: None,
+            move_costs: None,
+            objective: SolverKind::Shortest,
+            show_preview: false,
+            show_trace: false,
+            show_vacated: false,
+            show_astro_reach: false,
+            vacated: None,
+            show_coordinates: false,
+            show_move_space: false,
+            show_assist: false,
+            guided: false,
+            revealed: None,
+            goal_style: GoalStyle::Plain,
+            render_priority: RenderPriority::Pieces,
+            labels: Labels::default(),
+            move_feedback: MoveFeedback::Silent,
+            wrap_selection: true,
+            rejected_move: false,
+            event_handler: None,
+            spacing: false,
+            grid_lines: None,
+            fog: None,
+            time_limit: None,
+            show_remaining_optimal: false,
+            accessible: false,
+            warn_unsolvable: false,
+            generation: None,
+            board_sizes: None,
+            started: Instant::now(),
+            solve_time: None,
+            par,
+            undos_used: 0,
+            hardcore: false,
+            undo_limit: None,
+            dropped_moves: 0,
+            bookmark: None,
+            show_game_over_menu: true,
+            robot_moves: None,
+            show_step_numbers: false,
+            autoplay: false,
+            speed: 2,
+            inspector: None,
+            practice: None,
+            distances: RefCell::new(LruCache::new(DISTANCE_CACHE_SIZE)),
+            show_help: false,
+            pending_browse: None,
+            review_step: 0,
+            session: None,
+            auto_new_cancelled: false,
+            next_optimal: RefCell::new(LruCache::new(DISTANCE_CACHE_SIZE)),
+            status: None,
         };
 
         Ok(game)
     }
 
-    pub fn mode(&self) -> Mode {
-        self.mode
+    /// scores moves by `move_costs`, so that the walkthrough shows the cheapest solution
+    pub fn with_move_costs(mut self, move_costs: MoveCosts) -> Result<Self> {
+        let objective = SolverKind::Weighted(move_costs);
+        let solution = solve(&self.moves[0], objective)?;
+
+        self.move_costs = Some(move_costs);
+        self.objective = objective;
+        self.walkthrough = SolutionWalkthrough::new(solution);
+        Ok(self)
     }
 
-    fn state(&self) -> &State {
-        self.moves.last().expect("moves are never empty")
+    /// shows how many moves the optimal solution from the current position takes, after every move
+    pub fn with_remaining_optimal(mut self) -> Self {
+        self.show_remaining_optimal = true;
+        self
     }
 
-    pub fn select_next_character(&mut self) {
-        let num_robots = self.state().num_robots();
-        let next = match self.selected {
-            Selection::Astro if num_robots == 0 => Selection::Astro,
-            Selection::Astro => Selection::Robot(0),
-            Selection::Robot(n) if n + 1 == num_robots => Selection::Astro,
-            Selection::Robot(n) => Selection::Robot(n + 1),
+    /// lets `new_board` replace the board with another one generated with `options`.
+    /// `options` should be the ones the current board was generated with.
+    pub fn with_generation(mut self, options: GenerationOptions) -> Self {
+        self.generation = Some(options);
+        self
+    }
+
+    pub fn can_generate_new_board(&self) -> bool {
+        self.generation.is_some()
+    }
+
+    /// lets `resize_board` generate boards of any size in `sizes`, each way
+    pub fn with_board_sizes(mut self, sizes: RangeInclusive<usize>) -> Self {
+        self.board_sizes = Some(sizes);
+        self
+    }
+
+    pub fn can_resize_board(&self) -> bool {
+        self.can_generate_new_board() && self.board_sizes.is_some()
+    }
+
+    /// moves on to a new board a moment after each one is solved, keeping a tally of them all
+    pub fn with_auto_new(mut self) -> Result<Self> {
+        ensure!(
+            self.can_generate_new_board(),
+            "new boards can only be generated for randomly-generated boards"
+        );
+        self.session = Some(Session {
+            solved: 0,
+            moves: 0,
+            started: Instant::now(),
+        });
+        Ok(self)
+    }
+
+    /// how much longer the solved board stays up before `new_board` should replace it,
+    /// or `None` if it shouldn't be replaced
+    pub fn auto_new_wait(&self) -> Option<Duration> {
+        if self.session.is_none() || self.mode() != Mode::GameOver || self.auto_new_cancelled {
+            return None;
+        }
+        let solve_time = self.solve_time?;
+        let shown_for = self.started.elapsed().saturating_sub(solve_time);
+        Some(AUTO_NEW_PAUSE.saturating_sub(shown_for))
+    }
+
+    /// keeps the solved board up until a new one is asked for
+    pub fn cancel_auto_new(&mut self) {
+        self.auto_new_cancelled = true;
+    }
+
+    /// replaces the board with a newly-generated one of the same size.
+    ///
+    /// seeded generation moves on to the next seed, so that the boards still come in the same order.
+    pub fn new_board(&mut self) -> Result<()> {
+        let (rows, cols) = self.state().dims();
+        self.generate_board(rows, cols)
+    }
+
+    /// like `new_board`, but the new board is `delta` tiles bigger or smaller each way.
+    /// sizes outside those given to `with_board_sizes` are refused with a message,
+    /// and so are those that fail to generate, since another size can still be picked
+    pub fn resize_board(&mut self, delta: isize) {
+        let Some(sizes) = self.board_sizes.clone() else {
+            return;
+        };
+        let (rows, cols) = self.state().dims();
+        let resized =
+            [rows, cols].map(|n| n.checked_add_signed(delta).filter(|n| sizes.contains(n)));
+        let [Some(rows), Some(cols)] = resized else {
+            self.set_status(format!(
+                "boards can only be from {0}x{0} to {1}x{1}",
+                sizes.start(),
+                sizes.end()
+            ));
+            return;
         };
 
-        self.selected = next;
+        match self.generate_board(rows, cols) {
+            Ok(()) => self.set_status(format!("new {rows}x{cols} board")),
+            Err(err) => self.set_status(format!("no {rows}x{cols} board: {err}")),
+        }
     }
 
-    pub fn select_prev_character(&mut self) {
-        let num_robots = self.state().num_robots();
-        let prev = match self.selected {
-            Selection::Astro if num_robots == 0 => Selection::Astro,
-            Selection::Astro => Selection::Robot(num_robots - 1),
-            Selection::Robot(n) if n == 0 => Selection::Astro,
-            Selection::Robot(n) => Selection::Robot(n - 1),
+    fn generate_board(&mut self, rows: usize, cols: usize) -> Result<()> {
+        let Some(options) = &mut self.generation else {
+            return Ok(());
         };
+        if let Some(seed) = &mut options.seed {
+            *seed = seed.wrapping_add(1);
+        }
+        let options = options.clone();
 
-        self.selected = prev;
+        let generated = State::generate(rows, cols, &options)?;
+        self.load(generated.state)
     }
 
-    pub fn toggle_mode(&mut self) {
-        self.mode = match self.mode {
-            Mode::Playable => Mode::Walkthrough,
-            Mode::Walkthrough => Mode::Playable,
-            Mode::GameOver => Mode::GameOver,
+    /// how many steps per second the walkthrough autoplays at, up to `MAX_SPEED`
+    pub fn with_speed(mut self, speed: u32) -> Self {
+        self.speed = speed.clamp(1, MAX_SPEED);
+        self
+    }
+
+    pub fn is_autoplaying(&self) -> bool {
+        self.autoplay && self.mode == Mode::Walkthrough
+    }
+
+    /// how long to wait between autoplayed steps
+    pub fn autoplay_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.speed
+    }
+
+    /// starts or stops stepping through the walkthrough on its own
+    pub fn toggle_autoplay(&mut self) {
+        self.autoplay = !self.is_autoplaying();
+        //playing from the end starts over
+        if self.autoplay && self.walkthrough.is_at_end() {
+            self.walkthrough.current_step = 0;
         }
+        self.describe_speed();
     }
 
-    fn selected_pos(&self) -> Pos {
-        self.state().pos_of(self.selected)
+    /// changes the autoplay speed by `delta` steps per second, staying between 1 and `MAX_SPEED`
+    pub fn change_speed(&mut self, delta: i32) {
+        self.speed = self.speed.saturating_add_signed(delta).clamp(1, MAX_SPEED);
+        self.describe_speed();
     }
 
-    pub fn move_toward(&self, direction: Direction) -> Action {
-        let attempt = self.state().move_toward(self.selected_pos(), direction);
-        Action::Movement(attempt)
+    fn describe_speed(&mut self) {
+        let state = if self.is_autoplaying() {
+            "playing"
+        } else {
+            "paused"
+        };
+        self.set_status(format!("{state} at {} steps per second", self.speed));
     }
 
-    pub fn move_selection_to(&mut self, new_pos: Pos) {
-        let mut new_state = self.state().clone();
-        *new_state.pos_of_mut(self.selected) = new_pos;
+    /// the next autoplayed step, which stops autoplaying once the walkthrough is over
+    pub fn autoplay_step(&mut self) {
+        self.walkthrough.increment();
+        if self.walkthrough.is_at_end() {
+            self.autoplay = false;
+            self.describe_speed();
+        }
+    }
 
-        self.moves.push(new_state);
+    /// lets each robot be moved at most `moves` times, on this board and the ones loaded after it.
+    /// fails if the board can't be solved within that, or if moves are scored by cost,
+    /// since the cheapest solution within the limits isn't searched for.
+    pub fn with_robot_move_limit(mut self, moves: usize) -> Result<Self> {
+        ensure!(
+            self.move_costs.is_none(),
+            "move limits can't be combined with move costs"
+        );
 
-        if self.state().is_at_goal() {
-            self.mode = Mode::GameOver;
+        self.robot_moves = Some(moves);
+        let initial_state = self.moves[0].clone();
+        self.load(initial_state)?;
+        Ok(self)
+    }
+
+    /// how many times each piece can be moved on the current board, if it's limited
+    fn move_limits(&self) -> HashMap<Selection, usize> {
+        match self.robot_moves {
+            Some(moves) => solver::robot_move_limits(self.state().num_robots(), moves),
+            None => HashMap::new(),
         }
     }
 
-    pub fn restart(&mut self) {
-        self.moves.truncate(1);
-        self.walkthrough.current_step = 0;
-        self.mode = Mode::Playable;
+    /// how many more times `selection` can be moved, or `None` if it's unlimited.
+    /// undoing a move gives it back.
+    pub fn moves_left(&self, selection: Selection) -> Option<usize> {
+        let limit = *self.move_limits().get(&selection)?;
+        let used = self
+            .moves
+            .iter()
+            .tuple_windows()
+            .filter(|(s, t)| s.pos_of(selection) != t.pos_of(selection))
+            .count();
+        Some(limit.saturating_sub(used))
     }
 
-    pub fn draw(&self, stdout: &mut impl Write) -> Result<()> {
-        write!(stdout, "{}", clear::All)?;
-        let terminal_size = terminal_size()?;
+    /// disables undo, redo, restarting and playing from the walkthrough, so that every move counts
+    pub fn with_hardcore(mut self) -> Self {
+        self.hardcore = true;
+        self
+    }
 
-        match self.mode() {
-            Mode::Playable | Mode::GameOver => self.draw_game_state(stdout, terminal_size)?,
-            Mode::Walkthrough => self.draw_walkthrough(stdout, terminal_size)?,
+    /// only keeps enough history to undo `limit` moves, dropping older states as moves are made.
+    /// the number of moves used is remembered, so it can't be combined with robot move limits.
+    pub fn with_undo_limit(mut self, limit: usize) -> Result<Self> {
+        ensure!(
+            self.robot_moves.is_none(),
+            "an undo limit can't be combined with robot move limits"
+        );
+        self.undo_limit = Some(limit);
+        Ok(self)
+    }
+
+    /// the number of moves made since the start, including any dropped by the undo limit
+    fn num_moves(&self) -> usize {
+        self.moves.len() - 1 + self.dropped_moves
+    }
+
+    /// drops the oldest states after the initial one, beyond what the undo limit needs
+    fn trim_history(&mut self) {
+        let Some(limit) = self.undo_limit else {
+            return;
         };
+        //the current state along with one for each move that can be undone
+        let retained = limit + 1;
 
-        stdout.flush()?;
-        Ok(())
+        let excess = (self.moves.len() - 1).saturating_sub(retained);
+        self.moves.drain(1..1 + excess);
+        self.dropped_moves += excess;
+
+        //a bookmarked state that was dropped can't be returned to
+        if self
+            .bookmark
+            .is_some_and(|moves| moves <= self.dropped_moves)
+        {
+            self.bookmark = None;
+        }
     }
 
-    fn draw_walkthrough(&self, stdout: &mut impl Write, terminal_size: (u16, u16)) -> Result<()> {
-        let (rows, cols) = self.state().dims();
-        let changes: Vec<_> = State::pos_changes(&self.walkthrough.solution).try_collect()?;
+    /// stops selecting the next piece at the last one, and the previous piece at the first one,
+    /// giving the move feedback there rather than going around
+    pub fn without_selection_wrapping(mut self) -> Self {
+        self.wrap_selection = false;
+        self
+    }
 
-        let walkthrough_labels = {
-            let change_labels = changes.iter().map(ToString::to_string);
-            iter::once("STARTING POSITION".to_string()).chain(change_labels)
-        };
-        for (i, label) in walkthrough_labels.enumerate() {
-            center_cursor(stdout, terminal_size, u16::try_from(i)?)?;
+    /// doesn't list the keys for restarting, reviewing and quitting once the board is solved
+    pub fn without_game_over_menu(mut self) -> Self {
+        self.show_game_over_menu = false;
+        self
+    }
 
-            if self.walkthrough.current_step == i {
-                write_colored(stdout, label, color::Green)?;
-                writeln!(stdout)?;
-            } else {
-                writeln!(stdout, "{label}")?;
-            }
+    /// in hardcore mode, explains in the status line that `action` isn't allowed, and returns true
+    fn refuse_in_hardcore(&mut self, action: &str) -> bool {
+        if self.hardcore {
+            self.set_status(format!("{action} disabled in hardcore mode"));
         }
+        self.hardcore
+    }
 
-        let is_end_pos_of_prev_step = |pos: Pos| {
-            let step = self.walkthrough.current_step;
-            match step.checked_sub(1) {
-                Some(prev_step) => pos == changes[prev_step].1,
-                None => false,
-            }
-        };
+    /// warns in the status line whenever a move leaves the goal unreachable
+    pub fn with_unsolvable_warning(mut self) -> Self {
+        self.warn_unsolvable = true;
+        self
+    }
 
-        writeln!(stdout)?;
-        let offset_from_top = changes.len() + 2;
+    /// underlines highlighted pieces and gives the astro its own glyph at the goal,
+    /// so that nothing is told apart by color alone
+    pub fn with_accessible(mut self) -> Self {
+        self.accessible = true;
+        self
+    }
 
-        for y in 0..rows {
-            let adjusted_y = u16::try_from(y + offset_from_top)?;
-            center_cursor(stdout, terminal_size, adjusted_y)?;
+    /// makes the goals easier to spot, even with pieces on them
+    pub fn with_goal_style(mut self, goal_style: GoalStyle) -> Self {
+        self.goal_style = goal_style;
+        self
+    }
 
-            for x in 0..cols {
-                let pos = Pos { x, y };
-                let tile = self.walkthrough.state().tile_at(pos);
+    /// whether the goals or the pieces resting on them are drawn
+    pub fn with_render_priority(mut self, render_priority: RenderPriority) -> Self {
+        self.render_priority = render_priority;
+        self
+    }
 
-                if is_end_pos_of_prev_step(pos) {
-                    write_colored(stdout, tile, color::Red)?;
-                } else {
-                    write!(stdout, "{tile}")?;
-                }
-            }
+    pub fn with_move_feedback(mut self, move_feedback: MoveFeedback) -> Self {
+        self.move_feedback = move_feedback;
+        self
+    }
 
-            writeln!(stdout, "\r")?;
+    /// gives the move feedback on the next draw, without changing anything else
+    pub fn reject_move(&mut self) {
+        self.rejected_move = self.move_feedback != MoveFeedback::Silent;
+        self.emit(|_| GameEvent::InvalidMove);
+    }
+
+    /// calls `handler` with every `GameEvent` from now on, replacing any handler set before
+    pub fn set_event_handler(&mut self, handler: impl FnMut(GameEvent) + 'static) {
+        self.event_handler = Some(EventHandler(Box::new(handler)));
+    }
+
+    /// passes the event that `event` builds to the event handler. without a handler, it isn't built at all
+    fn emit(&mut self, event: impl FnOnce(&Self) -> GameEvent) {
+        //taken out while it runs, so that building the event can look at the game
+        if let Some(mut handler) = self.event_handler.take() {
+            (handler.0)(event(self));
+            self.event_handler = Some(handler);
         }
+    }
 
-        Ok(())
+    /// whether the next draw flashes the selected piece, which has to be drawn over once the flash is over
+    pub fn is_flashing(&self) -> bool {
+        self.rejected_move && self.move_feedback == MoveFeedback::Flash
     }
 
-    fn draw_game_state(&self, stdout: &mut impl Write, terminal_size: (u16, u16)) -> Result<()> {
-        let (rows, cols) = self.state().dims();
+    pub fn clear_rejected_move(&mut self) {
+        self.rejected_move = false;
+    }
 
-        for y in 0..rows {
-            center_cursor(stdout, terminal_size, u16::try_from(y)?)?;
+    /// names the pieces by `labels` wherever they're described
+    pub fn with_labels(mut self, labels: Labels) -> Self {
+        self.labels = labels;
+        self
+    }
 
-            for x in 0..cols {
-                let pos = Pos { x, y };
-                let tile = self.state().tile_at(pos);
+    /// puts a space between the tiles of each row, which doubles the width of the board
+    pub fn with_spacing(mut self) -> Self {
+        self.spacing = true;
+        self
+    }
 
-                if self.mode() == Mode::GameOver && tile == Tile::Astro {
-                    write_colored(stdout, tile, color::Green)?;
-                } else if pos == self.selected_pos() {
-                    write_colored(stdout, tile, color::Red)?;
-                } else {
-                    write!(stdout, "{tile}")?;
-                }
-            }
+    /// draws lines between the tiles, which doubles both the width and the height of the board
+    pub fn with_grid_lines(mut self, grid_lines: GridLines) -> Self {
+        self.grid_lines = Some(grid_lines);
+        self
+    }
 
-            writeln!(stdout, "\r")?;
+    /// hides every tile further than `radius` steps from the astro, counting along rows and columns,
+    /// until the board is solved. the goals and the selected piece are always shown.
+    pub fn with_fog(mut self, radius: usize) -> Self {
+        self.fog = Some(radius);
+        self
+    }
+
+    /// ends the game as out of time when `limit` runs out before the board is solved.
+    /// the clock is the same as the solve time's, so restarting or loading a board resets it
+    pub fn with_time_limit(mut self, limit: TimeLimit) -> Self {
+        self.time_limit = Some(limit);
+        self
+    }
+
+    /// how long the current board may take, if there's a time limit
+    fn time_limit(&self) -> Option<Duration> {
+        match self.time_limit? {
+            TimeLimit::Fixed(limit) => Some(limit),
+            TimeLimit::PerParMove(per_move) => Some(per_move * u32::try_from(self.par).ok()?),
         }
+    }
 
-        Ok(())
+    /// the whole seconds left to solve the board in, rounded up, while there's a time limit to play against
+    pub fn countdown(&self) -> Option<u64> {
+        if self.mode() != Mode::Playable {
+            return None;
+        }
+        let left = self.time_limit()?.saturating_sub(self.started.elapsed());
+        Some(left.as_secs() + u64::from(left.subsec_nanos() > 0))
     }
 
-    pub fn undo(&mut self) {
-        if self.moves.len() > 1 {
-            self.moves.pop();
+    /// ends the game once the time limit has run out, and returns whether it just did.
+    /// practice is left first, so that the board shown is the one really reached
+    pub fn check_time_limit(&mut self) -> bool {
+        if self.countdown() != Some(0) {
+            return false;
+        }
+
+        if self.is_practicing() {
+            self.toggle_practice();
         }
+        self.mode = Mode::TimeUp;
+        true
     }
 
-    pub fn walkthrough_prev(&mut self) {
-        self.walkthrough.decrement();
+    /// whether `pos` is hidden by the fog. only drawing is affected, never movement or solving
+    fn is_fogged(&self, pos: Pos) -> bool {
+        let Some(radius) = self.fog else {
+            return false;
+        };
+        self.mode() == Mode::Playable
+            && self
+                .state()
+                .astros()
+                .all(|astro| astro.manhattan_distance(pos) > radius)
+            && pos != self.selected_pos()
+            && !self.state().goal_positions().contains(&pos)
     }
 
-    pub fn walkthrough_next(&mut self) {
-        self.walkthrough.increment();
+    /// whether there's a gap (between the tiles of a row, between rows)
+    fn gaps(&self) -> (bool, bool) {
+        let lines = self.grid_lines.is_some();
+        (self.spacing || lines, lines)
     }
-}
 
-#[derive(PartialEq, Eq, Debug)]
-pub enum Action {
-    Movement(MovementAttempt),
+    /// writes what goes between two tiles of a row
+    fn write_tile_gap(&self, stdout: &mut impl Write) -> Result<()> {
+        match self.grid_lines {
+            Some(grid_lines) => write_colored(stdout, grid_lines.glyphs().0, GRID_LINE_COLOR),
+            None => write!(stdout, " ").map_err(Report::from),
+        }
+    }
 
-    NextCharacter,
-    PrevCharacter,
+    /// draws the line between two rows of `tiles` tiles, at `row_offset`
+    fn draw_grid_line(
+        &self,
+        stdout: &mut impl Write,
+        layout: &Layout,
+        row_offset: usize,
+        tiles: usize,
+    ) -> Result<()> {
+        let Some(grid_lines) = self.grid_lines else {
+            return Ok(());
+        };
+        let (_, horizontal, crossing) = grid_lines.glyphs();
+        let line = iter::repeat_n(horizontal, tiles).join(&crossing.to_string());
 
-    Undo,
-    Restart,
+        goto_row(stdout, layout, u16::try_from(row_offset)?)?;
+        write_colored(stdout, line, GRID_LINE_COLOR)
+    }
 
-    Exit,
+    /// the number of moves in the optimal solution from the current position,
+    /// or `None` if the goal can't be reached from here
+    pub fn remaining_optimal(&self) -> Option<usize> {
+        self.distance_from(self.state())
+    }
 
-    PrevWalkthroughStep,
-    NextWalkthroughStep,
-    ToggleMode,
-}
+    /// like `remaining_optimal`, but from any position. solves each position only once,
+    /// so that cycling through undo and redo doesn't re-solve.
+    fn distance_from(&self, state: &State) -> Option<usize> {
+        if let Some(&distance) = self.distances.borrow_mut().get(state) {
+            return distance;
+        }
 
-#[derive(Debug, Clone)]
-struct SolutionWalkthrough {
-    solution: Vec<State>,
-    current_step: usize,
-}
+        self.solve_and_remember(state)
+    }
 
-impl SolutionWalkthrough {
-    pub fn new(solution: Vec<State>) -> Self {
-        Self {
-            solution,
-            current_step: 0,
+    /// the position the next optimal move from `state` leads to,
+    /// or `None` if `state` is already at the goal or can't reach it
+    fn next_optimal_from(&self, state: &State) -> Option<State> {
+        if let Some(next) = self.next_optimal.borrow_mut().get(state) {
+            return Some(next.clone());
         }
+        //a known distance without a next position means there's no move to make
+        if self.distances.borrow_mut().get(state).is_some() {
+            return None;
+        }
+
+        self.solve_and_remember(state)?;
+        self.next_optimal.borrow_mut().get(state).cloned()
     }
 
-    fn state(&self) -> &State {
-        self.solution
-            .get(self.current_step)
-            .expect("indexing field is private")
+    /// solves `state` and remembers the distance and next position of every position on the way,
+    /// since the rest of an optimal solution is optimal from each of them too
+    fn solve_and_remember(&self, state: &State) -> Option<usize> {
+        let mut distances = self.distances.borrow_mut();
+        let Some(solution) = state.solve_from_here() else {
+            distances.put(state.clone(), None);
+            return None;
+        };
+
+        let mut next_optimal = self.next_optimal.borrow_mut();
+        for (i, position) in solution.iter().enumerate() {
+            distances.put(position.clone(), Some(solution.len() - 1 - i));
+            if let Some(next) = solution.get(i + 1) {
+                next_optimal.put(position.clone(), next.clone());
+            }
+        }
+
+        Some(solution.len() - 1)
     }
 
-    pub fn len(&self) -> usize {
-        self.solution.len()
+    pub fn move_costs(&self) -> Option<MoveCosts> {
+        self.move_costs
     }
 
-    pub fn decrement(&mut self) {
-        if self.current_step > 0 {
-            self.current_step -= 1;
+    /// plays through `boards` in order, moving on to the next one whenever a board is solved.
+    pub fn new_tutorial(boards: Vec<State>) -> Result<Self> {
+        let mut game = Game::new_sequence("tutorial", boards, 0)?;
+        //each board of the tutorial builds on the ones before it
+        if let Some(sequence) = &mut game.sequence {
+            sequence.browsable = false;
         }
+        Ok(game)
     }
 
-    pub fn increment(&mut self) {
-        if self.current_step < self.len() - 1 {
-            self.current_step += 1;
+    /// like `new_tutorial`, but starts from the board at index `start`,
+    /// labels the progress with `name`, and lets `browse_board` skip between the boards.
+    pub fn new_sequence(name: impl Into<String>, boards: Vec<State>, start: usize) -> Result<Self> {
+        let name = name.into();
+        let first = boards
+            .get(start)
+            .cloned()
+            .ok_or_else(|| eyre!("{name} has no board {}", start + 1))?;
+
+        let game = Game {
+            sequence: Some(BoardSequence {
+                name,
+                boards,
+                current: start,
+                browsable: true,
+            }),
+            ..Game::new(first)?
+        };
+
+        Ok(game)
+    }
+
+    /// replaces the board being played, discarding all progress on the current one
+    fn load(&mut self, initial_state: State) -> Result<()> {
+        let solution = match self.robot_moves {
+            Some(moves) => {
+                let limits = solver::robot_move_limits(initial_state.num_robots(), moves);
+                initial_state
+                    .solve_with_move_limits(&limits)
+                    .ok_or_else(|| {
+                        eyre!(
+                            "game cannot be solved with {moves} moves per robot from {}",
+                            initial_state.to_debug_line()
+                        )
+                    })?
+            }
+            None => solve(&initial_state, self.objective)?,
+        };
+        //with limits, par is the best that can be done within them
+        let par = match self.objective {
+            SolverKind::Shortest => solution.len() - 1,
+            _ => solve(&initial_state, SolverKind::Shortest)?.len() - 1,
+        };
+
+        //a solved board counts however it's left
+        if let Some(session) = &mut self.session {
+            if self.mode == Mode::GameOver {
+                session.solved += 1;
+                session.moves += self.moves.len() - 1 + self.dropped_moves;
+            }
         }
+        self.auto_new_cancelled = false;
+
+        self.moves = vec![initial_state];
+        self.dropped_moves = 0;
+        self.undone.clear();
+        self.vacated = None;
+        self.bookmark = None;
+        self.practice = None;
+        self.selected = Selection::Astro;
+        self.mode = Mode::Playable;
+        self.walkthrough = SolutionWalkthrough::new(solution);
+        self.started = Instant::now();
+        self.solve_time = None;
+        self.par = par;
+        self.undos_used = 0;
+
+        Ok(())
     }
-}
 
-fn center_cursor(stdout: &mut impl Write, term_dims: (u16, u16), row_offset: u16) -> Result<()> {
-    let (term_cols, term_rows) = term_dims;
-    let (mid_cols, mid_rows) = (term_cols / 2, term_rows / 2);
+    pub fn has_next_board(&self) -> bool {
+        self.sequence
+            .as_ref()
+            .is_some_and(|sequence| sequence.current + 1 < sequence.boards.len())
+    }
 
-    let goto_middle = cursor::Goto(mid_cols, mid_rows + row_offset);
-    write!(stdout, "{goto_middle}").map_err(Report::from)
-}
+    pub fn next_board(&mut self) -> Result<()> {
+        let Some(sequence) = &mut self.sequence else {
+            return Ok(());
+        };
+        let target = sequence.current + 1;
+        let Some(next) = sequence.boards.get(target).cloned() else {
+            return Ok(());
+        };
 
-fn write_colored(stdout: &mut impl Write, d: impl Display, color: impl color::Color) -> Result<()> {
-    let fg = color::Fg(color);
-    let color_reset = color::Fg(color::Reset);
-    write!(stdout, "{fg}{d}{color_reset}").map_err(Report::from)
+        //the sequence stays on the current board if the next one can't be loaded
+        if let Err(err) = self.load(next) {
+            self.set_status(format!("cannot load level {}: {err}", target + 1));
+            return Ok(());
+        }
+        if let Some(sequence) = &mut self.sequence {
+            sequence.current = target;
+        }
+        Ok(())
+    }
+
+    pub fn can_browse_boards(&self) -> bool {
+        self.sequence
+            .as_ref()
+            .is_some_and(|sequence| sequence.browsable)
+    }
+
+    /// whether moving to another board would lose anything, which it doesn't once the board is solved
+    fn has_progress(&self) -> bool {
+        !matches!(self.mode(), Mode::GameOver | Mode::TimeUp)
+            && (self.num_moves() > 0 || self.is_practicing())
+    }
+
+    /// moves `delta` boards through the sequence, unsolved or not.
+    /// if that would lose progress, it only asks for the same `delta` again to confirm.
+    pub fn browse_board(&mut self, delta: i32) -> Result<()> {
+        let Some(sequence) = self.sequence.as_ref().filter(|sequence| sequence.browsable) else {
+            return Ok(());
+        };
+        let len = sequence.boards.len();
+        let Some(target) = sequence
+            .current
+            .checked_add_signed(isize::try_from(delta)?)
+            .filter(|&target| target < len)
+        else {
+            let edge = if delta < 0 { "first" } else { "last" };
+            self.set_status(format!("already at the {edge} level"));
+            return Ok(());
+        };
+        let board = sequence.boards[target].clone();
+
+        if self.has_progress() {
+            if self.refuse_in_hardcore("leaving an unsolved level") {
+                return Ok(());
+            }
+            if self.pending_browse != Some(delta) {
+                self.pending_browse = Some(delta);
+                let key = if delta < 0 { '[' } else { ']' };
+                self.set_status(format!("press {key} again to abandon this level"));
+                return Ok(());
+            }
+        }
+        self.pending_browse = None;
+
+        //the sequence stays on the current board if the new one can't be loaded
+        if let Err(err) = self.load(board) {
+            self.set_status(format!("cannot load level {}: {err}", target + 1));
+            return Ok(());
+        }
+        if let Some(sequence) = &mut self.sequence {
+            sequence.current = target;
+        }
+        Ok(())
+    }
+
+    /// forgets a board change waiting to be confirmed, once anything else is done instead
+    pub fn cancel_browse(&mut self) {
+        self.pending_browse = None;
+    }
+
+    pub fn difficulty_stars(&self) -> u8 {
+        let score = solver::difficulty_score(&self.walkthrough.solution);
+        solver::difficulty_stars(score)
+    }
+
+    pub fn walkthrough(&self) -> &SolutionWalkthrough {
+        &self.walkthrough
+    }
+
+    /// the optimal solution from the initial state, including it
+    pub fn solution(&self) -> &[State] {
+        &self.walkthrough.solution
+    }
+
+    /// the key presses that play out the optimal solution, starting from a fresh game
+    pub fn solution_keystrokes(&self) -> Result<Vec<Key>> {
+        let solution = self.solution();
+        let mut keys = Vec::new();
+        let mut selected = Selection::Astro;
+
+        for (state, change) in iter::zip(solution, State::pos_changes(solution)) {
+            let change = change?;
+            let selection = state
+                .selection_at(change.0)
+                .ok_or_else(|| eyre!("no piece to move at {}", change.0))?;
+            let direction = change
+                .direction()
+                .ok_or_else(|| eyre!("{change} is not a straight line"))?;
+
+            let selections = state.selections().collect_vec();
+            keys.extend(selection_keys(
+                selected,
+                selection,
+                &selections,
+                self.wrap_selection,
+            ));
+            keys.push(direction_key(direction));
+            selected = selection;
+        }
+
+        Ok(keys)
+    }
+
+    /// the moves made so far in move notation, as `State::to_notation` writes it.
+    /// while practicing, only the moves made before practicing began.
+    pub fn to_notation(&self) -> Result<String> {
+        ensure!(
+            self.dropped_moves == 0,
+            "the oldest moves were dropped because of the undo limit"
+        );
+        let moves = match &self.practice {
+            Some((moves, _)) => moves,
+            None => &self.moves,
+        };
+        State::to_notation(moves)
+    }
+
+    /// writes the walkthrough's solution to `path`, one move per line, as `(x, y) => (x, y)`.
+    /// it can be loaded back along with the same board with `with_imported_solution`.
+    pub fn export_solution(&self, path: &Path) -> Result<()> {
+        let lines: Vec<String> = State::pos_changes(self.solution())
+            .map_ok(|change| format!("{change}\n"))
+            .try_collect()?;
+        fs::write(path, lines.concat())
+            .wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    /// replaces the walkthrough with the solution exported to `path`, and starts in the walkthrough.
+    /// fails if it isn't a legal solution of the initial board.
+    pub fn with_imported_solution(mut self, path: &Path) -> Result<Self> {
+        let file = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        //the lines written by `export_solution` are the only format with arrows made of `=>`
+        let solution = if file.contains("=>") {
+            let changes: Vec<PosChange> = file
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::parse)
+                .try_collect()?;
+            self.moves[0].replay(&changes)?
+        } else {
+            self.moves[0].replay_notation(&file)?
+        };
+        let last = solution.last().expect("replays are never empty");
+        ensure!(
+            last.is_at_goal(),
+            "the solution in {} doesn't reach the goal",
+            path.display()
+        );
+
+        self.walkthrough = SolutionWalkthrough::new(solution);
+        self.mode = Mode::Walkthrough;
+        Ok(self)
+    }
+
+    /// saves the current board and the number of moves made to a timestamped file
+    /// in the working directory, in a format that can be loaded with `--level`.
+    pub fn screenshot(&self) -> Result<PathBuf> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = PathBuf::from(format!("astro-{timestamp}.txt"));
+
+        let board = self.state().to_str_grid();
+        let num_moves = self.num_moves();
+        fs::write(&path, format!("{board}\n\nmoves: {num_moves}\n"))?;
+
+        Ok(path)
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    fn state(&self) -> &State {
+        self.moves.last().expect("moves are never empty")
+    }
+
+    pub fn select_next_character(&mut self) {
+        if self.refuse_without_robots() {
+            return;
+        }
+
+        let selections = self.state().selections().collect_vec();
+        let i = self.selection_index(&selections);
+        if !self.wrap_selection && i == selections.len() - 1 {
+            self.refuse_selection_wrap("last");
+            return;
+        }
+        self.selected = selections[(i + 1) % selections.len()];
+    }
+
+    pub fn select_prev_character(&mut self) {
+        if self.refuse_without_robots() {
+            return;
+        }
+
+        let selections = self.state().selections().collect_vec();
+        let i = self.selection_index(&selections);
+        if !self.wrap_selection && i == 0 {
+            self.refuse_selection_wrap("first");
+            return;
+        }
+        self.selected = selections[(i + selections.len() - 1) % selections.len()];
+    }
+
+    /// without selection wrapping, explains that the selection is already at the `end` piece,
+    /// giving the move feedback as well, since nothing changes on the board
+    fn refuse_selection_wrap(&mut self, end: &str) {
+        self.set_status(format!("already at the {end} piece"));
+        self.rejected_move = self.move_feedback != MoveFeedback::Silent;
+    }
+
+    /// where the selected piece is in `selections`, the order the selection cycles through
+    fn selection_index(&self, selections: &[Selection]) -> usize {
+        selections
+            .iter()
+            .position(|&selection| selection == self.selected)
+            .expect("the selected piece is on the board")
+    }
+
+    /// on a board without robots, explains in the status line that there's no other piece
+    /// to select, and returns true
+    fn refuse_without_robots(&mut self) -> bool {
+        let alone = self.state().num_selections() == 1;
+        if alone {
+            self.set_status(format!("only the {} on this board", self.labels.astro));
+        }
+        alone
+    }
+
+    /// switches between playing and the walkthrough. a solved board can still be reviewed,
+    /// and leaving the walkthrough goes back to the game over screen.
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            Mode::Playable | Mode::GameOver | Mode::Review | Mode::TimeUp => Mode::Walkthrough,
+            Mode::Walkthrough if self.state().is_at_goal() && !self.is_practicing() => {
+                Mode::GameOver
+            }
+            //the clock keeps running in the walkthrough
+            Mode::Walkthrough
+                if self
+                    .time_limit()
+                    .is_some_and(|limit| self.started.elapsed() >= limit) =>
+            {
+                Mode::TimeUp
+            }
+            Mode::Walkthrough => Mode::Playable,
+        }
+    }
+
+    /// switches between the solved board and the review of how it was solved
+    pub fn toggle_review(&mut self) {
+        match self.mode {
+            Mode::Review => self.mode = Mode::GameOver,
+            Mode::GameOver if self.dropped_moves > 0 => {
+                self.set_status(
+                    "the first moves weren't kept (--undo-limit), so they can't be reviewed",
+                );
+            }
+            Mode::GameOver => {
+                self.review_step = 0;
+                self.mode = Mode::Review;
+            }
+            _ => {}
+        }
+    }
+
+    /// steps `delta` steps through both solutions in the review, stopping at either end
+    pub fn review_step(&mut self, delta: isize) {
+        let steps = self.moves.len().max(self.walkthrough.len());
+        self.review_step = self.review_step.saturating_add_signed(delta).min(steps - 1);
+    }
+
+    pub fn toggle_preview(&mut self) {
+        self.show_preview = !self.show_preview;
+    }
+
+    pub fn toggle_trace(&mut self) {
+        self.show_trace = !self.show_trace;
+    }
+
+    pub fn toggle_vacated(&mut self) {
+        self.show_vacated = !self.show_vacated;
+    }
+
+    /// marks the tiles the astro can get to with the robots where they are now,
+    /// which shows when a robot has to move first
+    pub fn toggle_astro_reach(&mut self) {
+        self.show_astro_reach = !self.show_astro_reach;
+    }
+
+    pub fn toggle_coordinates(&mut self) {
+        self.show_coordinates = !self.show_coordinates;
+    }
+
+    /// switches the walkthrough to the next kind of optimal solution, keeping to the current step if it can.
+    /// the walkthrough keeps to move limits, so it can't be switched when there are any.
+    pub fn cycle_objective(&mut self) {
+        if self.robot_moves.is_some() {
+            self.set_status("the walkthrough keeps to the move limits");
+            return;
+        }
+
+        let objective = match self.objective {
+            SolverKind::Shortest => SolverKind::Weighted(self.move_costs.unwrap_or_default()),
+            SolverKind::Weighted(_) => SolverKind::MinRobotMoves,
+            SolverKind::MinRobotMoves => SolverKind::Shortest,
+        };
+        //every objective is optimal in something, so if one can solve the board they all can
+        let Some(solution) = objective.solve(&self.moves[0]) else {
+            self.set_status("game cannot be solved");
+            return;
+        };
+
+        let step = self.walkthrough.current_step.min(solution.len() - 1);
+        self.objective = objective;
+        self.walkthrough = SolutionWalkthrough::new(solution);
+        self.walkthrough.current_step = step;
+        self.set_status(format!("showing the {objective} solution"));
+    }
+
+    /// numbers the tiles the astro stopped on in the walkthrough's earlier steps
+    pub fn toggle_step_numbers(&mut self) {
+        self.show_step_numbers = !self.show_step_numbers;
+    }
+
+    pub fn is_practicing(&self) -> bool {
+        self.practice.is_some()
+    }
+
+    /// starts or stops moving pieces freely, one tile at a time.
+    /// nothing done while practicing counts, and stopping goes back to where practice started.
+    pub fn toggle_practice(&mut self) {
+        self.vacated = None;
+        match self.practice.take() {
+            Some((moves, undone)) => {
+                self.moves = moves;
+                self.undone = undone;
+            }
+            None => {
+                self.practice = Some((self.moves.clone(), mem::take(&mut self.undone)));
+            }
+        }
+    }
+
+    /// shades every tile any piece can stop on in one move
+    pub fn toggle_move_space(&mut self) {
+        self.show_move_space = !self.show_move_space;
+    }
+
+    /// starts with the assist shown, as `toggle_assist` does
+    pub fn with_assist(mut self) -> Self {
+        self.show_assist = true;
+        self
+    }
+
+    /// shades where the next optimal move starts and ends, after every move.
+    /// it gives the solution away, so it can't be shown in hardcore mode.
+    pub fn toggle_assist(&mut self) {
+        if !self.show_assist && self.refuse_in_hardcore("the assist") {
+            return;
+        }
+        self.show_assist = !self.show_assist;
+    }
+
+    /// builds the solution up with the player one move at a time: the next optimal move is shown,
+    /// and a move that leaves every optimal line is refused with a "try again"
+    pub fn with_guided(mut self) -> Self {
+        self.guided = true;
+        self
+    }
+
+    /// the move the guided mode asks for next, if it's on
+    fn guided_move(&self) -> Option<PosChange> {
+        if !self.guided || self.mode() != Mode::Playable || self.is_practicing() {
+            return None;
+        }
+
+        let next = self.next_optimal_from(self.state())?;
+        PosChange::try_from((self.state(), &next)).ok()
+    }
+
+    /// whether moving to `next` leaves one move fewer to the goal. there may be several
+    /// optimal moves, so this re-solves from `next` rather than comparing it to `guided_move`
+    fn stays_optimal(&self, next: &State) -> bool {
+        match self.distance_from(self.state()) {
+            Some(remaining) => self.distance_from(next) == remaining.checked_sub(1),
+            //nothing is optimal from a position that can't reach the goal
+            None => true,
+        }
+    }
+
+    /// draws the rest of the optimal solution over the board, or stops drawing it.
+    /// it's drawn until the position changes, or until a move is attempted.
+    pub fn toggle_solution(&mut self) {
+        if self.revealed_solution().is_some() {
+            self.hide_solution();
+            return;
+        }
+
+        //the walkthrough already has the solution from anywhere along it
+        let on_walkthrough = self
+            .walkthrough
+            .solution
+            .iter()
+            .position(|state| state == self.state());
+        let solution = match on_walkthrough {
+            Some(step) => Some(self.walkthrough.solution[step..].to_vec()),
+            None => self.solution_from_here(),
+        };
+
+        match solution {
+            Some(solution) => self.revealed = Some(solution),
+            None => self.set_status("no solution from here, undo or restart"),
+        }
+    }
+
+    pub fn hide_solution(&mut self) {
+        self.revealed = None;
+    }
+
+    /// the solution `toggle_solution` revealed, if it's still from the current position
+    fn revealed_solution(&self) -> Option<&[State]> {
+        self.revealed
+            .as_deref()
+            .filter(|solution| solution.first() == Some(self.state()))
+    }
+
+    /// every tile the astros pass through in `solution`, and where the robots that move end up
+    fn solution_overlay(solution: &[State]) -> (Vec<Pos>, Vec<Pos>) {
+        let mut trail = Vec::new();
+        for (s, t) in solution.iter().tuple_windows() {
+            for (from, to) in iter::zip(s.astros(), t.astros()) {
+                let Some(direction) = PosChange(from, to).direction() else {
+                    continue;
+                };
+                trail.extend(
+                    s.positions_in_path(from, direction)
+                        .take_while(|&pos| pos != to),
+                );
+                trail.push(to);
+            }
+        }
+
+        let (first, last) = (&solution[0], &solution[solution.len() - 1]);
+        let robot_ends = iter::zip(first.robots(), last.robots())
+            .filter(|(start, end)| start != end)
+            .map(|(_, &end)| end)
+            .collect();
+        (trail, robot_ends)
+    }
+
+    fn assist_fits(&self) -> bool {
+        let (rows, cols) = self.state().dims();
+        rows * cols <= ASSIST_MAX_TILES
+    }
+
+    /// where the piece the next optimal move is made with stands, and where it stops
+    fn assist_move(&self) -> Option<PosChange> {
+        if !self.show_assist || self.mode() != Mode::Playable || self.is_practicing() {
+            return None;
+        }
+        if !self.assist_fits() {
+            return None;
+        }
+
+        let next = self.next_optimal_from(self.state())?;
+        PosChange::try_from((self.state(), &next)).ok()
+    }
+
+    /// where the selected piece would stop if moved in each direction
+    fn preview_positions(&self) -> Vec<Pos> {
+        self.state()
+            .movement()
+            .directions()
+            .iter()
+            .filter_map(|&direction| match self.move_toward(direction) {
+                Action::Movement(MovementAttempt::Success(pos)) => Some(pos),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// like `preview_positions`, along with the direction the selected piece slides in to stop there
+    fn trace_arrows(&self) -> Vec<(Pos, Direction)> {
+        self.state()
+            .movement()
+            .directions()
+            .iter()
+            .filter_map(|&direction| match self.move_toward(direction) {
+                Action::Movement(MovementAttempt::Success(pos)) => Some((pos, direction)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn is_inspecting(&self) -> bool {
+        self.inspector.is_some()
+    }
+
+    /// shows or hides the inspection cursor, which starts on the selected piece
+    pub fn toggle_inspector(&mut self) {
+        self.inspector = match self.inspector {
+            Some(_) => None,
+            None => Some(self.selected_pos()),
+        };
+        self.describe_inspected();
+    }
+
+    /// moves the inspection cursor by one tile, staying on the grid
+    pub fn move_inspector(&mut self, direction: Direction) {
+        let Some(pos) = self.inspector else {
+            return;
+        };
+        if let Some(next) = self.state().positions_in_path(pos, direction).next() {
+            self.inspector = Some(next);
+        }
+        self.describe_inspected();
+    }
+
+    fn describe_inspected(&mut self) {
+        let Some(pos) = self.inspector else {
+            return;
+        };
+        let name = match self.state().selection_at(pos) {
+            _ if self.is_fogged(pos) => "hidden by the fog".to_string(),
+            Some(selection) => self.labels.name(selection),
+            None => match self.state().tile_at(pos) {
+                Tile::Goal => "goal",
+                Tile::Blocker => "blocker",
+                _ => "empty",
+            }
+            .to_string(),
+        };
+        self.set_status(format!("{pos}: {name}"));
+    }
+
+    /// whether the piece at `pos` is one of the pieces that have to reach a goal
+    fn must_reach_goal(&self, pos: Pos) -> bool {
+        let state = self.state();
+        match state.win_condition() {
+            WinCondition::AstroOnly => state.astros().contains(&pos),
+            WinCondition::AllPieces => state.selection_at(pos).is_some(),
+            WinCondition::Occupant(GoalOccupant::AnyRobot) => {
+                matches!(state.selection_at(pos), Some(Selection::Robot(_)))
+            }
+            WinCondition::Occupant(GoalOccupant::Robot(n)) => state.robots()[n] == pos,
+        }
+    }
+
+    /// whether the piece at `pos` is the one robot that has to reach the goal
+    fn is_target_robot(&self, pos: Pos) -> bool {
+        match self.state().win_condition() {
+            WinCondition::Occupant(GoalOccupant::Robot(n)) => self.state().robots()[n] == pos,
+            _ => false,
+        }
+    }
+
+    fn selected_pos(&self) -> Pos {
+        self.state().pos_of(self.selected)
+    }
+
+    /// whether the board has `Movement::EightWay`, for which the diagonal keys move pieces
+    pub fn can_move_diagonally(&self) -> bool {
+        self.state().movement() == Movement::EightWay
+    }
+
+    pub fn move_toward(&self, direction: Direction) -> Action {
+        let attempt = match self.practice {
+            Some(_) => self.state().step_toward(self.selected_pos(), direction),
+            None => self.state().move_toward(self.selected_pos(), direction),
+        };
+        Action::Movement(attempt)
+    }
+
+    pub fn move_selection_to(&mut self, new_pos: Pos) {
+        let mut new_state = self.state().clone();
+        //practice steps one tile at a time, so they never push anything
+        let placed = match self.practice {
+            Some(_) => new_state.try_place(self.selected, new_pos),
+            None => new_state.try_move(self.selected, new_pos),
+        };
+        //moves are only attempted onto free tiles, so this would be a bug elsewhere
+        if let Err(err) = placed {
+            self.set_status(format!("move rejected: {err}"));
+            self.emit(|_| GameEvent::InvalidMove);
+            return;
+        }
+        let from = self.selected_pos();
+
+        if self.is_practicing() {
+            self.undone.clear();
+            self.push_state(new_state);
+            self.vacated = Some(from);
+            self.emit(|_| GameEvent::Move(PosChange(from, new_pos)));
+            return;
+        }
+
+        //a pushed robot uses up one of its moves too, as it does for the solver
+        let pushed = PosChange(from, new_pos)
+            .direction()
+            .and_then(|direction| self.state().push_toward(from, direction))
+            .filter(|&(to, _)| to == new_pos)
+            .and_then(|(_, PosChange(robot, _))| self.state().selection_at(robot));
+        let exhausted = iter::once(self.selected)
+            .chain(pushed)
+            .find(|&selection| self.moves_left(selection) == Some(0));
+        if let Some(selection) = exhausted {
+            let name = self.labels.name(selection);
+            self.set_status(format!("{name} has no moves left"));
+            self.emit(|_| GameEvent::InvalidMove);
+            return;
+        }
+
+        if self.guided && !self.stays_optimal(&new_state) {
+            self.set_status("try again, that move isn't optimal");
+            self.reject_move();
+            return;
+        }
+
+        self.branch_history();
+        self.emit(|_| GameEvent::Move(PosChange(from, new_pos)));
+        self.push_state(new_state);
+        self.vacated = Some(from);
+
+        if self.guided && self.mode() == Mode::Playable {
+            self.set_status("correct");
+        }
+
+        if self.warn_unsolvable && self.distance_from(self.state()).is_none() {
+            self.set_status("no solution from here, undo or restart");
+        }
+    }
+
+    /// explains in the status line why the board can't be solved from here, if it can't
+    pub fn explain_unsolvable(&mut self) {
+        //a remembered distance saves looking at every position again
+        let reason = match self.distance_from(self.state()) {
+            Some(_) => None,
+            None => self.state().explain_unsolvable(),
+        };
+        match reason {
+            Some(reason) => self.set_status(format!("no solution: {reason}")),
+            None => self.set_status("there's still a solution from here"),
+        }
+    }
+
+    /// finishes the board from the current position with the optimal solution from here,
+    /// adding its moves after the ones already made. respects what's left of any move limits.
+    pub fn auto_solve(&mut self) {
+        if self.is_practicing() {
+            self.set_status("can't finish the board while practicing");
+            return;
+        }
+
+        let Some(solution) = self.solution_from_here() else {
+            self.set_status("no solution from here, undo or restart");
+            return;
+        };
+
+        self.branch_history();
+        //the solution starts with the current state
+        for state in solution.into_iter().skip(1) {
+            self.push_state(state);
+        }
+    }
+
+    /// the optimal solution from the current position, keeping to what's left of any move limits
+    fn solution_from_here(&self) -> Option<Vec<State>> {
+        let limits: HashMap<Selection, usize> = (0..self.state().num_robots())
+            .map(Selection::Robot)
+            .filter_map(|selection| Some((selection, self.moves_left(selection)?)))
+            .collect();
+        if limits.is_empty() {
+            self.state().solve_from_here()
+        } else {
+            self.state().solve_with_move_limits(&limits)
+        }
+    }
+
+    fn push_state(&mut self, state: State) {
+        self.moves.push(state);
+        //only a move the player makes marks where its piece was
+        self.vacated = None;
+        if !self.is_practicing() {
+            self.trim_history();
+        }
+
+        //reaching the goal while practicing doesn't end the game
+        if self.state().is_at_goal() && !self.is_practicing() {
+            self.mode = Mode::GameOver;
+            self.solve_time = Some(self.started.elapsed());
+            self.emit(|_| GameEvent::Win);
+        }
+    }
+
+    pub fn restart(&mut self) {
+        //while practicing, restarting only resets the practice
+        if let Some((moves, _)) = &self.practice {
+            self.moves = moves.clone();
+            self.undone.clear();
+            self.vacated = None;
+            return;
+        }
+
+        if self.refuse_in_hardcore("restart") {
+            return;
+        }
+
+        self.moves.truncate(1);
+        self.dropped_moves = 0;
+        self.undone.clear();
+        self.vacated = None;
+        self.bookmark = None;
+        self.walkthrough.current_step = 0;
+        self.mode = Mode::Playable;
+        self.started = Instant::now();
+        self.solve_time = None;
+        self.undos_used = 0;
+    }
+
+    /// starts timing the board from now, such as once the intro is out of the way
+    pub fn start_clock(&mut self) {
+        self.started = Instant::now();
+    }
+
+    /// how long it took to reach the goal, once it's reached
+    pub fn solve_time(&self) -> Option<Duration> {
+        self.solve_time
+    }
+
+    /// how play on the current board went so far, leaving out anything done while practicing
+    pub fn summary(&self) -> Summary {
+        let moves = match &self.practice {
+            Some((moves, _)) => moves,
+            None => &self.moves,
+        };
+
+        Summary {
+            solved: self.solve_time.is_some(),
+            moves: moves.len() - 1 + self.dropped_moves,
+            par: self.par,
+            undos: self.undos_used,
+            time: self.solve_time.unwrap_or_else(|| self.started.elapsed()),
+            closest: moves
+                .iter()
+                .map(State::manhattan_to_goal)
+                .min()
+                .expect("moves are never empty"),
+        }
+    }
+
+    /// the smallest terminal (cols, rows) the board can be played in: enough of it to see
+    /// where pieces are going, the clip indicators around it and the status line below it
+    pub fn min_terminal_size(&self) -> (u16, u16) {
+        let (rows, cols) = self.state().dims();
+        let (col_gaps, row_gaps) = self.gaps();
+        let cols = row_width(cols.min(MIN_VISIBLE_TILES), col_gaps) + 2;
+        let rows = row_width(rows.min(MIN_VISIBLE_TILES), row_gaps) + 3;
+        (
+            u16::try_from(cols).unwrap_or(u16::MAX),
+            u16::try_from(rows).unwrap_or(u16::MAX),
+        )
+    }
+
+    /// whether the terminal is at least `min_terminal_size`, or its size is unknown
+    pub fn fits_terminal(&self) -> bool {
+        self.fits(terminal_size().ok())
+    }
+
+    /// like `fits_terminal`, but for a terminal of `size` (cols, rows), or of unknown size if `None`
+    pub fn fits(&self, size: Option<(u16, u16)>) -> bool {
+        let (min_cols, min_rows) = self.min_terminal_size();
+        size.is_none_or(|(cols, rows)| cols >= min_cols && rows >= min_rows)
+    }
+
+    /// asks for a larger terminal instead of drawing anything else, if `size` is too small.
+    /// returns whether it did
+    fn draw_resize_prompt(
+        &self,
+        stdout: &mut impl Write,
+        size: Option<(u16, u16)>,
+    ) -> Result<bool> {
+        if self.fits(size) {
+            return Ok(false);
+        }
+
+        let (min_cols, min_rows) = self.min_terminal_size();
+        let prompt = format!("Please resize your terminal to at least {min_cols}x{min_rows}");
+        let term_cols = size.map_or(usize::MAX, |(cols, _)| usize::from(cols));
+
+        //wrapped at word boundaries, since the terminal may be narrower than the prompt
+        let mut lines: Vec<String> = Vec::new();
+        for word in prompt.split(' ') {
+            match lines.last_mut() {
+                Some(line) if line.len() + 1 + word.len() <= term_cols => {
+                    line.push(' ');
+                    line.push_str(word);
+                }
+                _ => lines.push(word.to_string()),
+            }
+        }
+
+        write!(stdout, "{}", clear::All)?;
+        for (i, line) in (1..).zip(lines) {
+            write!(stdout, "{}{line}", cursor::Goto(1, i))?;
+        }
+        stdout.flush()?;
+        Ok(true)
+    }
+
+    pub fn draw(&self, stdout: &mut impl Write) -> Result<()> {
+        self.draw_sized(stdout, terminal_size().ok())
+    }
+
+    /// like `draw`, but for a terminal of `size` (cols, rows) rather than the one it runs in,
+    /// so that the same game always draws the same output
+    pub fn draw_sized(&self, stdout: &mut impl Write, size: Option<(u16, u16)>) -> Result<()> {
+        if self.draw_resize_prompt(stdout, size)? {
+            return Ok(());
+        }
+
+        write!(stdout, "{}", clear::All)?;
+        //rendering doesn't depend on the terminal, e.g. when output is not a tty
+        let area = size.map(|size| Area {
+            size,
+            col_offset: 0,
+        });
+        self.draw_in(stdout, area)
+    }
+
+    /// like `draw`, but only in `area`, and without clearing the screen first,
+    /// so that several games can share the terminal
+    pub fn draw_in(&self, stdout: &mut impl Write, area: Option<Area>) -> Result<()> {
+        let (layout, free_row) = match self.mode() {
+            Mode::Playable | Mode::GameOver | Mode::TimeUp => self.draw_game_state(stdout, area)?,
+            Mode::Walkthrough => self.draw_walkthrough(stdout, area)?,
+            Mode::Review => self.draw_review(stdout, area)?,
+        };
+
+        if let Some(status) = &self.status {
+            goto_row(stdout, &layout, free_row)?;
+            write!(stdout, "{status}")?;
+        }
+
+        if self.show_help {
+            self.draw_help(stdout, area)?;
+        }
+
+        if self.rejected_move && self.move_feedback == MoveFeedback::Bell {
+            write!(stdout, "\x07")?;
+        }
+
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// draws the controls over the board, until `dismiss_help`
+    pub fn show_help(&mut self) {
+        self.show_help = true;
+    }
+
+    pub fn dismiss_help(&mut self) {
+        self.show_help = false;
+    }
+
+    pub fn is_showing_help(&self) -> bool {
+        self.show_help
+    }
+
+    /// the keys that do something in the current mode, with what they do
+    fn controls(&self) -> Vec<(&'static str, &'static str)> {
+        match self.mode() {
+            Mode::Playable => {
+                let mut controls = vec![("arrows", "move the selected piece")];
+                if self.can_move_diagonally() {
+                    controls.push(("7 9 1 3", "move diagonally"));
+                }
+                if self.state().num_selections() > 1 {
+                    controls.push(("z / x", "select the previous / next piece"));
+                }
+                controls.extend([
+                    ("u / y", "undo / redo, U / Y for all"),
+                    ("b / '", "bookmark / return to it"),
+                    ("r", "restart"),
+                    ("w", "walkthrough"),
+                    ("p", "preview where the piece stops"),
+                    ("t", "trace each way the piece slides"),
+                    ("l", "mark where the last piece moved from"),
+                    ("m", "shade where pieces can stop"),
+                    ("a", "mark where the astro can get to alone"),
+                    ("h", "shade the next optimal move"),
+                    ("g", "show the whole solution from here"),
+                    ("e", "practice, one tile at a time"),
+                    ("f", "finish with the optimal solution"),
+                    ("d", "explain why there's no solution"),
+                    ("i", "inspect tiles"),
+                    ("c", "coordinates"),
+                    ("s", "screenshot"),
+                ]);
+                if self.can_generate_new_board() {
+                    controls.push(("n", "new board"));
+                }
+                if self.can_resize_board() {
+                    controls.push(("+ / -", "new bigger / smaller board"));
+                }
+                if self.can_browse_boards() {
+                    controls.push(("[ / ]", "previous / next level"));
+                }
+                controls.push(("esc", "quit"));
+                controls
+            }
+            Mode::Walkthrough => vec![
+                ("z / x", "previous / next step"),
+                ("space", "autoplay"),
+                ("+ / -", "autoplay faster / slower"),
+                ("enter", "play on from this step"),
+                ("o", "switch what the solution is optimal in"),
+                ("p", "number the astro's steps"),
+                ("w", "back to the board"),
+                ("esc", "quit"),
+            ],
+            Mode::GameOver => {
+                let mut controls = vec![("r", "restart")];
+                if self.can_generate_new_board() {
+                    controls.push(("n", "new board"));
+                }
+                if self.can_resize_board() {
+                    controls.push(("+ / -", "new bigger / smaller board"));
+                }
+                if self.can_browse_boards() {
+                    controls.push(("[ / ]", "previous / next level"));
+                }
+                controls.extend([
+                    ("w", "walkthrough"),
+                    ("v", "review your moves against it"),
+                    ("c", "coordinates"),
+                    ("s", "screenshot"),
+                    ("q / esc", "quit"),
+                ]);
+                controls
+            }
+            Mode::TimeUp => {
+                let mut controls = vec![("r", "restart")];
+                if self.can_generate_new_board() {
+                    controls.push(("n", "new board"));
+                }
+                if self.can_resize_board() {
+                    controls.push(("+ / -", "new bigger / smaller board"));
+                }
+                if self.can_browse_boards() {
+                    controls.push(("[ / ]", "previous / next level"));
+                }
+                controls.extend([
+                    ("w", "walkthrough"),
+                    ("c", "coordinates"),
+                    ("s", "screenshot"),
+                    ("q / esc", "quit"),
+                ]);
+                controls
+            }
+            Mode::Review => vec![
+                ("z / x", "previous / next step"),
+                ("v", "back to the board"),
+                ("w", "walkthrough"),
+                ("esc", "quit"),
+            ],
+        }
+    }
+
+    /// a box listing `controls`, in the middle of `area` and over whatever's there
+    fn draw_help(&self, stdout: &mut impl Write, area: Option<Area>) -> Result<()> {
+        let controls = self.controls();
+        let key_width = controls.iter().map(|(key, _)| key.len()).max().unwrap_or(0);
+        let mut lines = vec!["CONTROLS".to_string(), String::new()];
+        lines.extend(
+            controls
+                .iter()
+                .map(|(key, action)| format!("{key:<key_width$}  {action}")),
+        );
+        lines.extend([String::new(), "any key to close".to_string()]);
+
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let border = format!("+{}+", "-".repeat(width + 2));
+        let layout = area_layout(area, (width + 4, lines.len() + 2));
+
+        goto_row(stdout, &layout, 0)?;
+        write!(stdout, "{border}")?;
+        for (row, line) in (1..).zip(&lines) {
+            goto_row(stdout, &layout, row)?;
+            write!(stdout, "| {line:<width$} |")?;
+        }
+        goto_row(stdout, &layout, u16::try_from(lines.len() + 1)?)?;
+        write!(stdout, "{border}")?;
+
+        Ok(())
+    }
+
+    /// shows `status` below the board, until it's cleared or replaced
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = Some(status.into());
+    }
+
+    pub fn clear_status(&mut self) {
+        self.status = None;
+    }
+
+    pub fn draw_intro(&self, stdout: &mut impl Write) -> Result<()> {
+        self.draw_intro_sized(stdout, terminal_size().ok())
+    }
+
+    /// like `draw_intro`, but for a terminal of `size`, as `draw_sized` is
+    pub fn draw_intro_sized(
+        &self,
+        stdout: &mut impl Write,
+        size: Option<(u16, u16)>,
+    ) -> Result<()> {
+        if self.draw_resize_prompt(stdout, size)? {
+            return Ok(());
+        }
+
+        write!(stdout, "{}", clear::All)?;
+
+        let stars = usize::from(self.difficulty_stars());
+        let rating = format!("{}{}", "★".repeat(stars), "☆".repeat(5 - stars));
+        let (rows, cols) = self.state().dims();
+
+        let mut lines = vec![
+            self.labels.title(),
+            String::new(),
+            format!("board: {rows}x{cols}"),
+            format!("difficulty: {rating}"),
+            match self.state().win_condition() {
+                WinCondition::AstroOnly if self.state().partner().is_some() => {
+                    format!("get both {}s to a goal", self.labels.astro)
+                }
+                WinCondition::AstroOnly => format!("get the {} to the goal", self.labels.astro),
+                WinCondition::AllPieces => "get every piece onto a goal".to_string(),
+                WinCondition::Occupant(GoalOccupant::AnyRobot) => {
+                    format!("get any {} to the goal", self.labels.robot)
+                }
+                WinCondition::Occupant(GoalOccupant::Robot(_)) => {
+                    format!("get the marked {} to the goal", self.labels.robot)
+                }
+            },
+            match self.state().stop_rule() {
+                StopRule::Adjacent => "pieces stop next to other pieces".to_string(),
+                StopRule::OnGoal => "pieces also stop on goals".to_string(),
+                StopRule::RobotsAvoidGoal => format!("{} can't stop on goals", self.labels.robots),
+            },
+        ];
+        if self.state().collision_rule() == CollisionRule::Push {
+            lines.push(format!(
+                "pieces push the {} they run into",
+                self.labels.robots
+            ));
+        }
+        if self.can_move_diagonally() {
+            lines.push("pieces also slide diagonally, with 7 9 1 3".to_string());
+        }
+        lines.extend([String::new(), "press any key to start".to_string()]);
+        let width = lines.iter().map(|line| line.chars().count()).max();
+        let layout = Layout::new(size, (width.unwrap_or(0), lines.len()));
+
+        for (i, line) in lines.iter().enumerate() {
+            goto_row(stdout, &layout, u16::try_from(i)?)?;
+            write!(stdout, "{line}")?;
+        }
+
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// the pieces that moved in each step of the walkthrough
+    fn walkthrough_changes(&self) -> Result<Vec<Vec<PosChange>>> {
+        self.walkthrough
+            .states()
+            .tuple_windows()
+            .map(|(s, t)| State::all_pos_changes(s, t))
+            .try_collect()
+    }
+
+    /// a description of each step from `states` that makes `changes`
+    fn step_labels<'a>(
+        &'a self,
+        states: &'a [State],
+        changes: &'a [Vec<PosChange>],
+    ) -> impl Iterator<Item = String> + 'a {
+        //each change is named after the piece that made it, in the state the step starts from
+        iter::zip(states, changes).map(|(state, step)| {
+            step.iter()
+                .map(|change| match state.selection_at(change.0) {
+                    Some(selection) => format!("{}: {change}", self.labels.name(selection)),
+                    None => change.to_string(),
+                })
+                .join(", ")
+        })
+    }
+
+    /// a description of each step of the walkthrough, starting with the initial state
+    fn walkthrough_labels(&self, changes: &[Vec<PosChange>]) -> Result<Vec<String>> {
+        let change_labels = self.step_labels(&self.walkthrough.solution, changes);
+        let starting_label = match self.move_costs {
+            Some(move_costs) => {
+                let cost = move_costs.path_cost(&self.walkthrough.solution)?;
+                format!("STARTING POSITION (cost: {cost})")
+            }
+            None => "STARTING POSITION".to_string(),
+        };
+
+        Ok(iter::once(starting_label).chain(change_labels).collect())
+    }
+
+    /// a plain-text render of each step of the walkthrough: the board, followed by its label.
+    /// each frame can be loaded as a level.
+    pub fn walkthrough_frames(&self) -> Result<Vec<String>> {
+        let labels = self.walkthrough_labels(&self.walkthrough_changes()?)?;
+        let frames = iter::zip(self.walkthrough.states(), labels)
+            .map(|(state, label)| format!("{}\n\n{label}\n", state.to_str_grid()))
+            .collect();
+
+        Ok(frames)
+    }
+
+    /// returns the layout it drew with, and the first row offset below the drawing
+    fn draw_walkthrough(
+        &self,
+        stdout: &mut impl Write,
+        area: Option<Area>,
+    ) -> Result<(Layout, u16)> {
+        let (rows, cols) = self.state().dims();
+        let changes = self.walkthrough_changes()?;
+        let walkthrough_labels = self.walkthrough_labels(&changes)?;
+        let offset_from_top = changes.len() + 2;
+
+        let (col_gaps, row_gaps) = self.gaps();
+        let height = row_width(rows, row_gaps);
+
+        let width = walkthrough_labels.iter().map(String::len).max();
+        let layout = area_layout(
+            area,
+            (
+                width.unwrap_or(0).max(row_width(cols, col_gaps)),
+                offset_from_top + height,
+            ),
+        );
+
+        for (i, label) in walkthrough_labels.into_iter().enumerate() {
+            goto_row(stdout, &layout, u16::try_from(i)?)?;
+
+            if self.walkthrough.current_step == i {
+                self.write_highlighted(stdout, label, color::Green)?;
+                writeln!(stdout)?;
+            } else {
+                writeln!(stdout, "{label}")?;
+            }
+        }
+
+        let is_end_pos_of_prev_step = |pos: Pos| {
+            let step = self.walkthrough.current_step;
+            match step.checked_sub(1) {
+                Some(prev_step) => changes[prev_step].iter().any(|change| pos == change.1),
+                None => false,
+            }
+        };
+
+        //where the pieces that moved in the previous step slid through, including where they started
+        let trail = match self.walkthrough.current_step.checked_sub(1) {
+            Some(prev_step) => changes[prev_step]
+                .iter()
+                .filter_map(|change| {
+                    let direction = change.direction()?;
+                    let passed = self
+                        .walkthrough
+                        .state()
+                        .positions_in_path(change.0, direction)
+                        .take_while(|&pos| pos != change.1);
+                    Some(iter::once(change.0).chain(passed))
+                })
+                .flatten()
+                .collect_vec(),
+            None => Vec::new(),
+        };
+
+        //the first step the astro was at each position in, up to the current step
+        let mut step_numbers = HashMap::new();
+        if self.show_step_numbers {
+            let visited = self.walkthrough.states().map(|state| state.astro);
+            for (step, pos) in visited.enumerate().take(self.walkthrough.current_step) {
+                step_numbers.entry(pos).or_insert(step);
+            }
+        }
+
+        writeln!(stdout)?;
+
+        for y in 0..rows {
+            if row_gaps && y > 0 {
+                self.draw_grid_line(
+                    stdout,
+                    &layout,
+                    offset_from_top + tile_offset(y, true) - 1,
+                    cols,
+                )?;
+            }
+            let adjusted_y = u16::try_from(offset_from_top + tile_offset(y, row_gaps))?;
+            goto_row(stdout, &layout, adjusted_y)?;
+
+            for x in 0..cols {
+                if col_gaps && x > 0 {
+                    self.write_tile_gap(stdout)?;
+                }
+
+                let pos = Pos { x, y };
+                let tile = self.walkthrough.state().tile_at(pos);
+                let glyph = self.tile_glyph(self.walkthrough.state(), pos, tile);
+
+                let step_number = step_numbers.get(&pos).filter(|_| tile != Tile::Robot);
+
+                if is_end_pos_of_prev_step(pos) {
+                    self.write_highlighted(stdout, glyph, color::Red)?;
+                } else if let (Some(&step), Tile::Empty | Tile::Goal) = (step_number, tile) {
+                    write_colored(stdout, step_glyph(step), STEP_NUMBER_COLOR)?;
+                } else if trail.contains(&pos) && tile == Tile::Empty {
+                    write_colored(stdout, TRAIL_GLYPH, TRAIL_COLOR)?;
+                } else if trail.contains(&pos) {
+                    write_colored(stdout, glyph, TRAIL_COLOR)?;
+                } else if tile == Tile::Blocker {
+                    write_colored(stdout, tile, BLOCKER_COLOR)?;
+                } else {
+                    self.write_tile(stdout, self.walkthrough.state(), pos, tile)?;
+                }
+            }
+
+            writeln!(stdout, "\r")?;
+        }
+
+        let free_row = u16::try_from(offset_from_top + height + 1)?;
+        Ok((layout, free_row))
+    }
+
+    /// draws the moves that solved the board and the walkthrough's solution side by side,
+    /// both at `review_step`. the steps where they make different moves are highlighted.
+    /// returns the layout it drew with, and the first row offset below the drawing
+    fn draw_review(&self, stdout: &mut impl Write, area: Option<Area>) -> Result<(Layout, u16)> {
+        let solutions = [self.moves.as_slice(), self.walkthrough.solution.as_slice()];
+        let changes: Vec<Vec<Vec<PosChange>>> = solutions
+            .iter()
+            .map(|states| {
+                states
+                    .iter()
+                    .tuple_windows()
+                    .map(|(s, t)| State::all_pos_changes(s, t))
+                    .try_collect()
+            })
+            .try_collect()?;
+        let [mine, optimal] = solutions;
+        let titles = [
+            format!("YOUR MOVES ({})", mine.len() - 1),
+            format!("OPTIMAL ({})", optimal.len() - 1),
+        ];
+        //the shorter solution is padded with blank steps
+        let steps = mine.len().max(optimal.len());
+        let columns = iter::zip(solutions, &changes)
+            .map(|(states, changes)| {
+                iter::once("STARTING POSITION".to_string())
+                    .chain(self.step_labels(states, changes))
+                    .pad_using(steps, |_| String::new())
+                    .collect_vec()
+            })
+            .collect_vec();
+        let diverges =
+            |step: usize| step > 0 && changes[0].get(step - 1) != changes[1].get(step - 1);
+
+        let (rows, cols) = self.state().dims();
+        let (col_gaps, row_gaps) = self.gaps();
+        let column_width = iter::once(row_width(cols, col_gaps))
+            .chain(
+                titles
+                    .iter()
+                    .chain(columns.iter().flatten())
+                    .map(String::len),
+            )
+            .max()
+            .unwrap_or(0);
+        let gutter = column_width + REVIEW_GAP;
+        let board_top = steps + 2;
+        let height = board_top + row_width(rows, row_gaps);
+        let layout = area_layout(area, (gutter + column_width, height));
+
+        for (i, (title, labels)) in iter::zip(&titles, &columns).enumerate() {
+            let column = layout.shifted(u16::try_from(gutter * i)?);
+            goto_row(stdout, &column, 0)?;
+            write!(stdout, "{title}")?;
+
+            for (step, label) in labels.iter().enumerate() {
+                goto_row(stdout, &column, u16::try_from(step + 1)?)?;
+                if step == self.review_step {
+                    write!(stdout, "{}", style::Invert)?;
+                }
+                if diverges(step) {
+                    self.write_highlighted(stdout, label, color::Red)?;
+                } else {
+                    write!(stdout, "{label}")?;
+                }
+                write!(stdout, "{}", style::NoInvert)?;
+            }
+
+            //a solution that's already over stays at its last step
+            let states = solutions[i];
+            let step = self.review_step.min(states.len() - 1);
+            let moved = step
+                .checked_sub(1)
+                .map(|prev| changes[i][prev].iter().map(|change| change.1).collect_vec())
+                .unwrap_or_default();
+            self.draw_board(stdout, &column, board_top, &states[step], &moved)?;
+        }
+
+        let free_row = u16::try_from(height + 1)?;
+        Ok((layout, free_row))
+    }
+
+    /// draws `state` from `row_offset` down, with the pieces at `highlighted` in red
+    fn draw_board(
+        &self,
+        stdout: &mut impl Write,
+        layout: &Layout,
+        row_offset: usize,
+        state: &State,
+        highlighted: &[Pos],
+    ) -> Result<()> {
+        let (rows, cols) = state.dims();
+        let (col_gaps, row_gaps) = self.gaps();
+
+        for y in 0..rows {
+            if row_gaps && y > 0 {
+                self.draw_grid_line(stdout, layout, row_offset + tile_offset(y, true) - 1, cols)?;
+            }
+            goto_row(
+                stdout,
+                layout,
+                u16::try_from(row_offset + tile_offset(y, row_gaps))?,
+            )?;
+
+            for x in 0..cols {
+                if col_gaps && x > 0 {
+                    self.write_tile_gap(stdout)?;
+                }
+
+                let pos = Pos { x, y };
+                let tile = state.tile_at(pos);
+                if highlighted.contains(&pos) {
+                    self.write_highlighted(stdout, self.tile_glyph(state, pos, tile), color::Red)?;
+                } else if tile == Tile::Blocker {
+                    write_colored(stdout, tile, BLOCKER_COLOR)?;
+                } else {
+                    self.write_tile(stdout, state, pos, tile)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// returns the layout it drew with, and the first row offset below the drawing
+    fn draw_game_state(
+        &self,
+        stdout: &mut impl Write,
+        area: Option<Area>,
+    ) -> Result<(Layout, u16)> {
+        let dims = self.state().dims();
+        let (rows, cols) = dims;
+
+        //the labels are followed by a gap, which is where the clip indicators go
+        let (label_cols, label_rows) = if self.show_coordinates {
+            (digits(rows - 1) + 1, digits(cols - 1) + 1)
+        } else {
+            (0, 0)
+        };
+        let gaps = self.gaps();
+        let (col_gaps, row_gaps) = gaps;
+        let outer_layout = area_layout(
+            area,
+            (
+                row_width(cols, col_gaps) + label_cols,
+                row_width(rows, row_gaps) + label_rows,
+            ),
+        );
+        let layout = outer_layout.inset(u16::try_from(label_cols)?, u16::try_from(label_rows)?);
+
+        let focus = self.inspector.unwrap_or_else(|| self.selected_pos());
+        let (available_cols, available_rows) = layout.available();
+        let available = (
+            tiles_fitting(available_cols, col_gaps),
+            tiles_fitting(available_rows, row_gaps),
+        );
+        let viewport = Viewport::new(dims, available, focus);
+        if self.show_coordinates {
+            draw_coordinates(
+                stdout,
+                &outer_layout,
+                &viewport,
+                (label_cols, label_rows),
+                gaps,
+            )?;
+        }
+        let previews = if self.show_preview && self.mode() == Mode::Playable {
+            self.preview_positions()
+        } else {
+            Vec::new()
+        };
+        let trace = if self.show_trace && self.mode() == Mode::Playable {
+            self.trace_arrows()
+        } else {
+            Vec::new()
+        };
+        let trace_arrow = |pos: Pos| {
+            trace
+                .iter()
+                .find(|&&(p, _)| p == pos)
+                .map(|(_, direction)| direction.arrow())
+        };
+        let astro_reach = if self.show_astro_reach && self.mode() == Mode::Playable {
+            self.state().astro_reachable_positions()
+        } else {
+            HashSet::new()
+        };
+        let reachable = if self.show_move_space && self.mode() == Mode::Playable {
+            self.state().reachable_tiles()
+        } else {
+            Vec::new()
+        };
+        let (revealed_trail, revealed_robots) = match self.revealed_solution() {
+            Some(solution) if self.mode() == Mode::Playable => Game::solution_overlay(solution),
+            _ => (Vec::new(), Vec::new()),
+        };
+        let assist = self.assist_move().or_else(|| self.guided_move());
+        let assisted = |pos: Pos| {
+            assist
+                .as_ref()
+                .is_some_and(|PosChange(from, to)| pos == *from || pos == *to)
+        };
+        let reach_color = |pos: Pos| -> Option<&dyn color::Color> {
+            let mut reaching = reachable.iter().filter(|(_, p)| *p == pos);
+            if reaching
+                .clone()
+                .any(|(s, _)| matches!(s, Selection::Astro | Selection::Partner))
+            {
+                Some(&ASTRO_REACH_COLOR)
+            } else if reaching.next().is_some() {
+                Some(&ROBOT_REACH_COLOR)
+            } else {
+                None
+            }
+        };
+
+        for (row_offset, y) in viewport.ys.clone().enumerate() {
+            let row_offset = tile_offset(row_offset, row_gaps);
+            if row_gaps && row_offset > 0 {
+                self.draw_grid_line(stdout, &layout, row_offset - 1, viewport.xs.len())?;
+            }
+            goto_row(stdout, &layout, u16::try_from(row_offset)?)?;
+
+            for x in viewport.xs.clone() {
+                if col_gaps && x > viewport.xs.start {
+                    self.write_tile_gap(stdout)?;
+                }
+
+                let pos = Pos { x, y };
+                let tile = self.state().tile_at(pos);
+                let glyph = self.tile_glyph(self.state(), pos, tile);
+                //underneath whatever the tile is drawn as, unless it's shaded by the move space too
+                let shaded = assisted(pos) && colors_enabled();
+                if shaded {
+                    write!(stdout, "{}", color::Bg(ASSIST_COLOR))?;
+                }
+
+                let fogged = self.is_fogged(pos);
+                if self.inspector == Some(pos) {
+                    let glyph = if fogged { FOG_GLYPH.to_string() } else { glyph };
+                    write!(stdout, "{}{glyph}{}", style::Invert, style::NoInvert)?;
+                } else if fogged {
+                    write_colored(stdout, FOG_GLYPH, FOG_COLOR)?;
+                } else if self.mode() == Mode::GameOver
+                    && self.must_reach_goal(pos)
+                    && self.state().goal_positions().contains(&pos)
+                {
+                    let glyph = match tile {
+                        Tile::Astro if self.accessible => ASTRO_AT_GOAL_GLYPH.to_string(),
+                        Tile::Robot if self.accessible => ROBOT_AT_GOAL_GLYPH.to_string(),
+                        _ => glyph,
+                    };
+                    write_colored(stdout, glyph, color::Green)?;
+                } else if pos == self.selected_pos() && self.is_flashing() {
+                    write!(stdout, "{}", style::Invert)?;
+                    self.write_highlighted(stdout, glyph, color::Red)?;
+                    write!(stdout, "{}", style::NoInvert)?;
+                } else if pos == self.selected_pos() {
+                    self.write_highlighted(stdout, glyph, color::Red)?;
+                } else if self.is_target_robot(pos) {
+                    self.write_highlighted(stdout, glyph, TARGET_COLOR)?;
+                } else if tile == Tile::Blocker {
+                    write_colored(stdout, tile, BLOCKER_COLOR)?;
+                } else if revealed_robots.contains(&pos) && matches!(tile, Tile::Empty | Tile::Goal)
+                {
+                    write_colored(stdout, REVEALED_ROBOT_GLYPH, TRAIL_COLOR)?;
+                } else if revealed_trail.contains(&pos) && tile == Tile::Empty {
+                    write_colored(stdout, TRAIL_GLYPH, TRAIL_COLOR)?;
+                } else if revealed_trail.contains(&pos) && tile == Tile::Goal {
+                    write_colored(stdout, tile, TRAIL_COLOR)?;
+                } else if self.show_vacated && self.vacated == Some(pos) && tile == Tile::Empty {
+                    write_colored(stdout, VACATED_GLYPH, VACATED_COLOR)?;
+                } else if let Some(arrow) = trace_arrow(pos) {
+                    write_colored(stdout, arrow, TRACE_COLOR)?;
+                } else if previews.contains(&pos) {
+                    //landing on the goal is worth seeing, so it keeps its glyph
+                    let glyph = if tile == Tile::Goal { 'X' } else { '*' };
+                    write_colored(stdout, glyph, PREVIEW_COLOR)?;
+                } else if astro_reach.contains(&pos) && matches!(tile, Tile::Empty | Tile::Goal) {
+                    let glyph = if tile == Tile::Goal {
+                        'X'
+                    } else {
+                        ASTRO_REACH_GLYPH
+                    };
+                    write_colored(stdout, glyph, ASTRO_REACH_COLOR)?;
+                } else if let Some(reach_color) = reach_color(pos) {
+                    write_on_color(stdout, glyph, reach_color)?;
+                } else if self.goal_style != GoalStyle::Plain
+                    && self.state().goal_positions().contains(&pos)
+                {
+                    self.write_goal(stdout, tile, glyph)?;
+                } else {
+                    self.write_tile(stdout, self.state(), pos, tile)?;
+                }
+
+                if shaded {
+                    write!(stdout, "{}", color::Bg(color::Reset))?;
+                }
+            }
+
+            writeln!(stdout, "\r")?;
+        }
+
+        draw_clip_indicators(stdout, &layout, &viewport, dims, gaps)?;
+
+        //lines below the grid, leaving a gap for the bottom clip indicator
+        let mut footer_rows = (row_width(viewport.ys.len(), row_gaps) + 1..).map(u16::try_from);
+        //the widest of the board and the lines below it so far
+        let mut width = row_width(cols, col_gaps);
+
+        if let Some(solve_time) = self.solve_time {
+            let moves = self.num_moves();
+            let efficiency = self.par * 100 / moves.max(1);
+            let stats = [
+                format!("moves: {moves} (par {}, {efficiency}% efficient)", self.par),
+                format!("undos: {}", self.undos_used),
+                format!("time: {}", minutes_seconds(solve_time)),
+            ];
+
+            for line in stats {
+                goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+                write!(stdout, "{line}")?;
+                width = width.max(line.len());
+            }
+        }
+
+        let clock = match self.countdown() {
+            Some(seconds) => Some(format!(
+                "time left: {}",
+                minutes_seconds(Duration::from_secs(seconds))
+            )),
+            None if self.mode() == Mode::TimeUp => Some("Out of time!".to_string()),
+            None => None,
+        };
+        if let Some(line) = clock {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+            write!(stdout, "{line}")?;
+            width = width.max(line.len());
+        }
+
+        if let Some(session) = &self.session {
+            let (mut solved, mut moves) = (session.solved, session.moves);
+            if self.mode() == Mode::GameOver {
+                solved += 1;
+                moves += self.num_moves();
+            }
+            let time = minutes_seconds(session.started.elapsed());
+            let lines = [
+                self.auto_new_wait()
+                    .map(|_| "a new board is coming up, any key to stay".to_string()),
+                Some(format!("session: {solved} solved, {moves} moves, {time}")),
+            ];
+
+            for line in lines.into_iter().flatten() {
+                goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+                write!(stdout, "{line}")?;
+                width = width.max(line.len());
+            }
+        }
+
+        //moving on to the next board of a sequence takes any key instead
+        if self.show_game_over_menu && self.mode() == Mode::GameOver && !self.has_next_board() {
+            let restart = (!self.hardcore).then_some("r: restart");
+            let new_board = self.can_generate_new_board().then_some("n: new board");
+            let menu = [
+                restart,
+                new_board,
+                Some("w: walkthrough"),
+                Some("v: review"),
+                Some("q: quit"),
+            ];
+            let menu = menu.into_iter().flatten().collect_vec();
+
+            //centered under the board and stats as a block, after a blank line
+            let menu_width = menu.iter().map(|item| item.len()).max().unwrap_or(0);
+            let indent = width.saturating_sub(menu_width) / 2;
+            footer_rows.next();
+            for item in menu {
+                goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+                write!(stdout, "{:indent$}{item}", "")?;
+            }
+        }
+
+        if self.mode() == Mode::Playable {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+            let num_robots = self.state().num_robots();
+            let selected = match self.selected {
+                Selection::Astro | Selection::Partner => self.labels.name(self.selected),
+                Selection::Robot(n) => format!("{} {}/{num_robots}", self.labels.robot, n + 1),
+            };
+            write!(stdout, "selected: {selected}")?;
+        }
+
+        if self.is_practicing() {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+            write!(stdout, "PRACTICE: nothing counts (e to stop, r to reset)")?;
+        }
+
+        if let Some(solution) = self
+            .revealed_solution()
+            .filter(|_| self.mode() == Mode::Playable)
+        {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+            let moves = solution.len() - 1;
+            write!(stdout, "SOLUTION: {moves} moves from here (g to hide)")?;
+        }
+
+        if self.show_assist && self.mode() == Mode::Playable && !self.is_practicing() {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+            if self.assist_fits() {
+                write!(stdout, "ASSIST: next optimal move shaded (h to hide)")?;
+            } else {
+                write!(stdout, "ASSIST: board too large to solve every move")?;
+            }
+        }
+
+        if let Some(change) = self.guided_move() {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+            let name = self
+                .state()
+                .selection_at(change.0)
+                .map(|selection| self.labels.name(selection));
+            match (name, change.direction()) {
+                (Some(name), Some(direction)) => {
+                    write!(stdout, "GUIDED: move the {name} {}", direction.arrow())?;
+                }
+                _ => write!(stdout, "GUIDED: move {change}")?,
+            }
+        }
+
+        if self.robot_moves.is_some() {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+
+            let moves_left = (0..self.state().num_robots())
+                .filter_map(|n| self.moves_left(Selection::Robot(n)))
+                .join(" ");
+            write!(stdout, "{} moves left: {moves_left}", self.labels.robot)?;
+        }
+
+        if self.show_remaining_optimal {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+
+            let remaining = self.remaining_optimal();
+            let label = match remaining {
+                Some(remaining) => format!("optimal moves left: {remaining}"),
+                None => "no solution from here".to_string(),
+            };
+            //the previous position is always solvable, since the current one was reached from it.
+            //it's unknown once the undo limit dropped it
+            let prev_remaining = self
+                .moves
+                .len()
+                .checked_sub(2)
+                .filter(|&prev| prev > 0 || self.dropped_moves == 0)
+                .and_then(|prev| self.distance_from(&self.moves[prev]));
+
+            match (prev_remaining, remaining) {
+                (Some(prev), Some(current)) if current < prev => {
+                    write_colored(stdout, label, color::Green)?
+                }
+                (Some(prev), Some(current)) if current > prev => {
+                    write_colored(stdout, label, color::Red)?
+                }
+                (Some(_), None) => write_colored(stdout, label, color::Red)?,
+                _ => write!(stdout, "{label}")?,
+            }
+        }
+
+        if let Some(sequence) = &self.sequence {
+            goto_row(stdout, &layout, footer_rows.next().expect("unbounded")?)?;
+
+            let progress = format!(
+                "{} {}/{}",
+                sequence.name,
+                sequence.current + 1,
+                sequence.boards.len()
+            );
+            if self.has_next_board() && self.mode() == Mode::GameOver {
+                write!(
+                    stdout,
+                    "{progress}: solved! press any key for the next board"
+                )?;
+            } else {
+                write!(stdout, "{progress}")?;
+            }
+        }
+
+        let free_row = footer_rows.next().expect("unbounded")?;
+        Ok((layout, free_row))
+    }
+
+    /// writes `d` in `color`, and also underlined in accessible mode
+    fn write_highlighted(
+        &self,
+        stdout: &mut impl Write,
+        d: impl Display,
+        color: impl color::Color,
+    ) -> Result<()> {
+        if !self.accessible {
+            return write_colored(stdout, d, color);
+        }
+
+        write!(stdout, "{}", style::Underline)?;
+        write_colored(stdout, d, color)?;
+        write!(stdout, "{}", style::NoUnderline).map_err(Report::from)
+    }
+
+    /// draws a goal tile according to `goal_style`, along with the piece on it if there is one.
+    /// `glyph` is what the tile is drawn as otherwise, see `tile_glyph`
+    fn write_goal(&self, stdout: &mut impl Write, tile: Tile, glyph: String) -> Result<()> {
+        //blinking is hard to read, so the accessible mode sticks to bold
+        let emphasis = match self.goal_style {
+            GoalStyle::Blink if !self.accessible => style::Blink.to_string(),
+            _ => style::Bold.to_string(),
+        };
+        write!(stdout, "{emphasis}")?;
+
+        match tile {
+            Tile::Goal => write_colored(stdout, tile, GOAL_COLOR)?,
+            //the goal is hidden under the piece, so it shows through as the background
+            _ => {
+                let glyph = match tile {
+                    Tile::Astro if self.accessible => ASTRO_AT_GOAL_GLYPH.to_string(),
+                    Tile::Robot if self.accessible => ROBOT_AT_GOAL_GLYPH.to_string(),
+                    _ => glyph,
+                };
+                write_on_color(stdout, glyph, GOAL_COLOR)?;
+            }
+        }
+
+        write!(stdout, "{}", style::Reset).map_err(Report::from)
+    }
+
+    /// the glyph a piece resting on a goal is drawn with when the goal takes priority,
+    /// or `None` if `tile` is drawn as itself
+    fn on_goal_glyph(&self, state: &State, pos: Pos, tile: Tile) -> Option<char> {
+        if self.render_priority == RenderPriority::Pieces || !state.goal_positions().contains(&pos)
+        {
+            return None;
+        }
+
+        match tile {
+            Tile::Astro => Some(ASTRO_ON_GOAL_GLYPH),
+            Tile::Partner => Some(PARTNER_ON_GOAL_GLYPH),
+            Tile::Robot => Some(ROBOT_ON_GOAL_GLYPH),
+            _ => None,
+        }
+    }
+
+    /// what `tile`, at `pos` of `state`, is drawn as, whichever color it's drawn in
+    fn tile_glyph(&self, state: &State, pos: Pos, tile: Tile) -> String {
+        self.on_goal_glyph(state, pos, tile)
+            .map_or_else(|| tile.to_string(), String::from)
+    }
+
+    /// writes a tile that isn't otherwise emphasized. a piece marked on its goal takes the goal color
+    fn write_tile(
+        &self,
+        stdout: &mut impl Write,
+        state: &State,
+        pos: Pos,
+        tile: Tile,
+    ) -> Result<()> {
+        match self.on_goal_glyph(state, pos, tile) {
+            Some(glyph) => write_colored(stdout, glyph, GOAL_COLOR),
+            None => write!(stdout, "{tile}").map_err(Report::from),
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if self.refuse_in_hardcore("undo") {
+            return;
+        }
+
+        if self.undo_move() {
+            if !self.is_practicing() {
+                self.undos_used += 1;
+            }
+        } else if let (Some(limit), true) = (self.undo_limit, self.dropped_moves > 0) {
+            self.set_status(format!("no more history to undo (--undo-limit {limit})"));
+        }
+    }
+
+    /// like `restart`, except that the undone moves can still be redone afterwards
+    pub fn undo_all(&mut self) {
+        if self.refuse_in_hardcore("undo") {
+            return;
+        }
+
+        let mut undid = false;
+        while self.undo_move() {
+            undid = true;
+        }
+
+        //undoing everything at once counts as a single undo
+        if undid && !self.is_practicing() {
+            self.undos_used += 1;
+        }
+    }
+
+    /// returns whether there was a move to undo
+    fn undo_move(&mut self) -> bool {
+        //undoing down to the initial state would skip over the dropped moves
+        let oldest = if self.dropped_moves > 0 { 2 } else { 1 };
+        if self.moves.len() <= oldest {
+            return false;
+        }
+
+        let state = self.moves.pop().expect("moves are never empty");
+        self.undone.push(Undone {
+            state,
+            selected: self.selected,
+            mode: self.mode,
+        });
+        self.vacated = None;
+        self.follow_walkthrough();
+        self.emit(|_| GameEvent::Undo);
+        true
+    }
+
+    /// while the moves so far are the start of the walkthrough's solution,
+    /// keeps the walkthrough at the same step as the board, so that it goes on from where the player is
+    fn follow_walkthrough(&mut self) {
+        if !self.is_practicing()
+            && self.dropped_moves == 0
+            && self.walkthrough.solution.starts_with(&self.moves)
+        {
+            self.walkthrough.current_step = self.moves.len() - 1;
+        }
+    }
+
+    /// a new move branches off the history, so the undone moves can't be redone anymore,
+    /// and a bookmark among them is gone with them
+    fn branch_history(&mut self) {
+        self.undone.clear();
+        if self.bookmark.is_some_and(|moves| moves > self.num_moves()) {
+            self.bookmark = None;
+        }
+    }
+
+    /// marks the current position, to come back to with `return_to_bookmark`
+    pub fn set_bookmark(&mut self) {
+        if self.is_practicing() {
+            self.set_status("no bookmarks while practicing");
+            return;
+        }
+
+        let moves = self.num_moves();
+        self.bookmark = Some(moves);
+        self.set_status(format!("bookmarked after {moves} moves"));
+    }
+
+    /// undoes back to the bookmarked position, or redoes forward to it if it was undone.
+    /// like `undo_all`, going back counts as a single undo
+    pub fn return_to_bookmark(&mut self) {
+        if self.is_practicing() {
+            self.set_status("no bookmarks while practicing");
+            return;
+        }
+        let Some(bookmark) = self.bookmark else {
+            self.set_status("no bookmark, b to set one");
+            return;
+        };
+        if bookmark == self.num_moves() {
+            self.set_status("already at the bookmark");
+            return;
+        }
+        if self.refuse_in_hardcore("returning to a bookmark") {
+            return;
+        }
+
+        if bookmark < self.num_moves() {
+            while self.num_moves() > bookmark && self.undo_move() {}
+            self.undos_used += 1;
+        } else {
+            while self.num_moves() < bookmark && self.redo_move() {}
+        }
+        self.set_status(format!("back at the bookmark after {bookmark} moves"));
+    }
+
+    pub fn redo(&mut self) {
+        if self.refuse_in_hardcore("redo") {
+            return;
+        }
+
+        self.redo_move();
+    }
+
+    /// returns whether there was a move to redo
+    fn redo_move(&mut self) -> bool {
+        let Some(undone) = self.undone.pop() else {
+            return false;
+        };
+
+        self.mode = undone.mode;
+        self.push_state(undone.state);
+        self.selected = undone.selected;
+        true
+    }
+
+    pub fn redo_all(&mut self) {
+        if self.refuse_in_hardcore("redo") {
+            return;
+        }
+
+        while !self.undone.is_empty() && self.mode == Mode::Playable {
+            self.redo();
+        }
+    }
+
+    pub fn walkthrough_prev(&mut self) {
+        self.walkthrough.decrement();
+    }
+
+    pub fn walkthrough_next(&mut self) {
+        self.walkthrough.increment();
+    }
+
+    /// continues playing from the walkthrough's current step, as if the player had made those moves.
+    pub fn play_from_walkthrough(&mut self) {
+        if self.refuse_in_hardcore("playing from the walkthrough") {
+            return;
+        }
+
+        let state = self.walkthrough.state().clone();
+        self.mode = Mode::Playable;
+        //playing on from the walkthrough is real play
+        if self.is_practicing() {
+            self.toggle_practice();
+        }
+
+        if state != *self.state() {
+            self.branch_history();
+            self.push_state(state);
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Action {
+    Movement(MovementAttempt),
+
+    NextCharacter,
+    PrevCharacter,
+
+    Undo,
+    UndoAll,
+    Redo,
+    RedoAll,
+    Restart,
+    Bookmark,
+    ReturnToBookmark,
+
+    Exit,
+
+    PrevWalkthroughStep,
+    NextWalkthroughStep,
+    PlayFromWalkthroughStep,
+    ToggleMode,
+
+    ToggleReview,
+    ReviewStep(isize),
+
+    NextBoard,
+    NewBoard,
+    BrowseBoard(i32),
+    ResizeBoard(isize),
+
+    Screenshot,
+    TogglePreview,
+    ToggleTrace,
+    ToggleVacated,
+    ToggleMoveSpace,
+    ToggleAstroReach,
+    ToggleAssist,
+    ToggleSolution,
+    ToggleStepNumbers,
+    ToggleCoordinates,
+    ToggleInspector,
+    MoveInspector(Direction),
+    ToggleAutoplay,
+    AutoSolve,
+    ExplainUnsolvable,
+    TogglePractice,
+    CycleObjective,
+    ChangeSpeed(i32),
+    ShowHelp,
+}
+
+/// what `Game` tells the handler set with `Game::set_event_handler` about,
+/// so that frontends other than the terminal one can react to play.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    /// the selected piece moved, whether it's a real move or a practice step.
+    /// a robot it pushed along the way isn't reported separately, so there's one of these for each move
+    Move(PosChange),
+    /// the board was solved
+    Win,
+    /// a move was taken back, once for each move when several are undone at once
+    Undo,
+    /// a move was attempted that can't be made
+    InvalidMove,
+}
+
+/// a boxed closure can't be printed, so this stands in for it in `Game`'s `Debug` output
+struct EventHandler(Box<dyn FnMut(GameEvent)>);
+
+impl fmt::Debug for EventHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventHandler")
+    }
+}
+
+/// how a game on one board went, as `Game::summary` reports it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub solved: bool,
+    pub moves: usize,
+    pub par: usize,
+    pub undos: usize,
+    /// until the goal was reached, or until now if it wasn't
+    pub time: Duration,
+    /// the nearest the astro got to a goal, as `State::manhattan_to_goal` measures it.
+    /// 0 once solved. only the moves still in the history count, which matters under an undo limit
+    pub closest: usize,
+}
+
+/// steps through an optimal solution, one state at a time.
+#[derive(Debug, Clone)]
+pub struct SolutionWalkthrough {
+    solution: Vec<State>,
+    current_step: usize,
+}
+
+impl SolutionWalkthrough {
+    pub fn new(solution: Vec<State>) -> Self {
+        Self {
+            solution,
+            current_step: 0,
+        }
+    }
+
+    fn state(&self) -> &State {
+        self.solution
+            .get(self.current_step)
+            .expect("indexing field is private")
+    }
+
+    /// every state of the solution in order, starting with the initial state
+    pub fn states(&self) -> impl Iterator<Item = &State> + '_ {
+        self.solution.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.solution.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.solution.is_empty()
+    }
+
+    pub fn decrement(&mut self) {
+        if self.current_step > 0 {
+            self.current_step -= 1;
+        }
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.current_step + 1 >= self.len()
+    }
+
+    pub fn increment(&mut self) {
+        if self.current_step < self.len() - 1 {
+            self.current_step += 1;
+        }
+    }
+}
+
+/// the largest (rows, cols) of a grid that can be drawn in the middle of a terminal of `terminal_size`,
+/// leaving a line below it for messages.
+/// `gaps` is whether there's a gap between the tiles of a row, and between rows.
+pub fn max_fitting_dims(terminal_size: (u16, u16), gaps: (bool, bool)) -> (usize, usize) {
+    let (cols, rows) = Layout::centered_capacity(terminal_size);
+    let (col_gaps, row_gaps) = gaps;
+    (
+        tiles_fitting(rows.saturating_sub(2), row_gaps),
+        tiles_fitting(cols, col_gaps),
+    )
+}
+
+/// the fewest `z`/`x` presses that change the selection from `from` to `to`,
+/// on a board whose pieces are `selections`.
+/// without `wrap`, the selection can't go past the first or last piece, so only one direction works.
+fn selection_keys(
+    from: Selection,
+    to: Selection,
+    selections: &[Selection],
+    wrap: bool,
+) -> impl Iterator<Item = Key> {
+    //the selection order that `select_next_character` cycles through
+    let index = |selection| {
+        selections
+            .iter()
+            .position(|&s| s == selection)
+            .expect("a piece on the board")
+    };
+    let num_selections = selections.len();
+
+    if !wrap {
+        let (from, to) = (index(from), index(to));
+        let (key, presses) = if from <= to {
+            ('x', to - from)
+        } else {
+            ('z', from - to)
+        };
+        return iter::repeat_n(Key::Char(key), presses);
+    }
+
+    let forward = (index(to) + num_selections - index(from)) % num_selections;
+    let backward = (num_selections - forward) % num_selections;
+    let (key, presses) = if forward <= backward {
+        ('x', forward)
+    } else {
+        ('z', backward)
+    };
+
+    iter::repeat_n(Key::Char(key), presses)
+}
+
+fn direction_key(direction: Direction) -> Key {
+    match direction {
+        Direction::Up => Key::Up,
+        Direction::Down => Key::Down,
+        Direction::Left => Key::Left,
+        Direction::Right => Key::Right,
+        //as on a numeric keypad
+        Direction::UpLeft => Key::Char('7'),
+        Direction::UpRight => Key::Char('9'),
+        Direction::DownLeft => Key::Char('1'),
+        Direction::DownRight => Key::Char('3'),
+    }
+}
+
+/// the shortest solution gives up after `SOLVE_MAX_NODES` positions or `SOLVE_TIMEOUT`,
+/// whichever comes first.
+fn solve(state: &State, objective: SolverKind) -> Result<Vec<State>, GameError> {
+    let unsolvable = || GameError::Unsolvable {
+        state: state.to_debug_line(),
+    };
+    if objective != SolverKind::Shortest {
+        return objective.solve(state).ok_or_else(unsolvable);
+    }
+
+    let started = Instant::now();
+    match state.solve_before(SOLVE_MAX_NODES, started + SOLVE_TIMEOUT) {
+        SolveOutcome::Solved(solution) => Ok(solution),
+        SolveOutcome::Unsolved => Err(unsolvable()),
+        SolveOutcome::Aborted(explored) => Err(GameError::GaveUp {
+            explored,
+            elapsed: started.elapsed(),
+        }),
+    }
+}
+
+/// like `Layout::new`, but in `area` rather than the whole terminal
+fn area_layout(area: Option<Area>, content: (usize, usize)) -> Layout {
+    match area {
+        Some(area) => Layout::new(Some(area.size), content).shifted(area.col_offset),
+        None => Layout::new(None, content),
+    }
+}
+
+fn goto_row(stdout: &mut impl Write, layout: &Layout, row_offset: u16) -> Result<()> {
+    write!(stdout, "{}", layout.goto(0, row_offset)).map_err(Report::from)
+}
+
+/// writes the index of each visible column above the grid, one digit per line,
+/// and the index of each visible row to its left.
+/// `label_size` is the (cols, rows) taken up by the labels, including the gap before the grid,
+/// and `gaps` is whether there's a gap between the tiles of a row, and between rows.
+fn draw_coordinates(
+    stdout: &mut impl Write,
+    layout: &Layout,
+    viewport: &Viewport,
+    label_size: (usize, usize),
+    gaps: (bool, bool),
+) -> Result<()> {
+    let (label_cols, label_rows) = label_size;
+    let (col_gaps, row_gaps) = gaps;
+    let digit_rows = label_rows - 1;
+
+    for digit_row in 0..digit_rows {
+        write!(
+            stdout,
+            "{}",
+            layout.goto(u16::try_from(label_cols)?, u16::try_from(digit_row)?)
+        )?;
+
+        //the top line has the most significant digits
+        let place = 10usize.pow(u32::try_from(digit_rows - 1 - digit_row)?);
+        for x in viewport.xs.clone() {
+            if col_gaps && x > viewport.xs.start {
+                write!(stdout, " ")?;
+            }
+
+            if x >= place || place == 1 {
+                write!(stdout, "{}", x / place % 10)?;
+            } else {
+                write!(stdout, " ")?;
+            }
+        }
+    }
+
+    let width = label_cols - 1;
+    for (row_offset, y) in viewport.ys.clone().enumerate() {
+        let row = u16::try_from(label_rows + tile_offset(row_offset, row_gaps))?;
+        write!(stdout, "{}{y:>width$}", layout.goto(0, row))?;
+    }
+
+    Ok(())
+}
+
+/// the cells a row of `tiles` tiles takes up
+fn row_width(tiles: usize, spacing: bool) -> usize {
+    if spacing {
+        (tiles * 2).saturating_sub(1)
+    } else {
+        tiles
+    }
+}
+
+/// how many tiles of a row fit in `cells` cells
+fn tiles_fitting(cells: usize, spacing: bool) -> usize {
+    if spacing {
+        cells.saturating_add(1) / 2
+    } else {
+        cells
+    }
+}
+
+/// the cells before the tile at `index` of a row
+fn tile_offset(index: usize, spacing: bool) -> usize {
+    if spacing {
+        index * 2
+    } else {
+        index
+    }
+}
+
+/// a single character for a step number: digits, then lowercase letters, then `+` for anything larger
+fn step_glyph(step: usize) -> char {
+    u32::try_from(step)
+        .ok()
+        .and_then(|step| char::from_digit(step, 36))
+        .unwrap_or('+')
+}
+
+/// the number of decimal digits in `n`
+fn digits(n: usize) -> usize {
+    n.checked_ilog10().map_or(1, |log| log as usize + 1)
+}
+
+/// marks each side of the viewport beyond which part of the grid is not shown.
+fn draw_clip_indicators(
+    stdout: &mut impl Write,
+    layout: &Layout,
+    viewport: &Viewport,
+    (rows, cols): (usize, usize),
+    (col_gaps, row_gaps): (bool, bool),
+) -> Result<()> {
+    let (origin_col, origin_row) = layout.origin();
+    let width = u16::try_from(row_width(viewport.xs.len(), col_gaps))?;
+    let height = u16::try_from(row_width(viewport.ys.len(), row_gaps))?;
+
+    //cursor positions are 1-based, so there's no room above or left of row/column 1
+    if viewport.is_clipped_top() && origin_row > 1 {
+        for col in origin_col..origin_col + width {
+            write!(stdout, "{}^", cursor::Goto(col, origin_row - 1))?;
+        }
+    }
+    if viewport.is_clipped_bottom(rows) {
+        for col in origin_col..origin_col + width {
+            write!(stdout, "{}v", cursor::Goto(col, origin_row + height))?;
+        }
+    }
+    if viewport.is_clipped_left() && origin_col > 1 {
+        for row in origin_row..origin_row + height {
+            write!(stdout, "{}<", cursor::Goto(origin_col - 1, row))?;
+        }
+    }
+    if viewport.is_clipped_right(cols) {
+        for row in origin_row..origin_row + height {
+            write!(stdout, "{}>", cursor::Goto(origin_col + width, row))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// formats `duration` as mm:ss
+fn minutes_seconds(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// whether anything is drawn in color. shared by every game, since it's about the terminal
+pub fn set_colors(enabled: bool) {
+    COLORS.store(enabled, Ordering::Relaxed);
+}
+
+/// whether colors are written, or only the plain text
+pub fn colors_enabled() -> bool {
+    COLORS.load(Ordering::Relaxed)
+}
+
+/// writes `d` in `color`, or just `d` when colors are disabled
+pub fn write_colored(
+    stdout: &mut impl Write,
+    d: impl Display,
+    color: impl color::Color,
+) -> Result<()> {
+    if !colors_enabled() {
+        return write!(stdout, "{d}").map_err(Report::from);
+    }
+
+    let fg = color::Fg(color);
+    let color_reset = color::Fg(color::Reset);
+    write!(stdout, "{fg}{d}{color_reset}").map_err(Report::from)
+}
+
+/// like `write_colored`, but colors the background behind `d`
+fn write_on_color(
+    stdout: &mut impl Write,
+    d: impl Display,
+    color: impl color::Color,
+) -> Result<()> {
+    if !colors_enabled() {
+        return write!(stdout, "{d}").map_err(Report::from);
+    }
+
+    let bg = color::Bg(color);
+    let bg_reset = color::Bg(color::Reset);
+    write!(stdout, "{bg}{d}{bg_reset}").map_err(Report::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SELECTIONS: [Selection; 4] = [
+        Selection::Astro,
+        Selection::Robot(0),
+        Selection::Robot(1),
+        Selection::Robot(2),
+    ];
+
+    fn keys(from: Selection, to: Selection, wrap: bool) -> Vec<Key> {
+        selection_keys(from, to, &SELECTIONS, wrap).collect()
+    }
+
+    /// the text on each row of the screen after `output` is written to it, with the
+    /// cursor movements followed and every other escape sequence left out
+    fn screen(output: &[u8]) -> Vec<String> {
+        let output = String::from_utf8(output.to_vec()).expect("utf-8 output");
+        let mut rows: Vec<Vec<char>> = Vec::new();
+        let (mut row, mut col) = (0, 0);
+
+        let mut chars = output.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => {
+                    let mut sequence = String::new();
+                    for c in chars.by_ref() {
+                        sequence.push(c);
+                        if c.is_ascii_alphabetic() {
+                            break;
+                        }
+                    }
+                    if let Some(goto) = sequence.strip_prefix('[').and_then(|s| s.strip_suffix('H'))
+                    {
+                        let (r, c) = goto.split_once(';').unwrap_or(("1", "1"));
+                        row = r.parse::<usize>().expect("a row") - 1;
+                        col = c.parse::<usize>().expect("a column") - 1;
+                    }
+                }
+                '\n' => {
+                    row += 1;
+                    col = 0;
+                }
+                '\r' => col = 0,
+                '\x07' => {}
+                c => {
+                    if rows.len() <= row {
+                        rows.resize(row + 1, Vec::new());
+                    }
+                    let line = &mut rows[row];
+                    if line.len() <= col {
+                        line.resize(col + 1, ' ');
+                    }
+                    line[col] = c;
+                    col += 1;
+                }
+            }
+        }
+        rows.into_iter()
+            .map(|line| line.into_iter().collect::<String>().trim_end().to_string())
+            .collect()
+    }
+
+    const SIZE: (u16, u16) = (80, 24);
+
+    /// the screen's rows, without the whitespace around them, once `game` is drawn on it
+    fn drawn(game: &Game) -> Vec<String> {
+        set_colors(false);
+        let mut output = Vec::new();
+        game.draw_sized(&mut output, Some(SIZE)).expect("drawn");
+        trimmed(&output)
+    }
+
+    fn trimmed(output: &[u8]) -> Vec<String> {
+        screen(output)
+            .into_iter()
+            .map(|row| row.trim().to_string())
+            .collect()
+    }
+
+    fn shows(lines: &[String], expected: &[&str]) -> bool {
+        lines
+            .windows(expected.len())
+            .any(|window| window == expected)
+    }
+
+    /// a board the astro solves by sliding right onto the goal
+    fn game() -> Game {
+        let state = State::from_str_grid("A.XB\n....\nR...").expect("a valid grid");
+        Game::new(state).expect("a solvable board")
+    }
+
+    fn solved() -> Game {
+        let mut game = game();
+        let Action::Movement(MovementAttempt::Success(pos)) = game.move_toward(Direction::Right)
+        else {
+            panic!("the astro can move right");
+        };
+        game.move_selection_to(pos);
+        game
+    }
+
+    #[test]
+    fn draws_the_board_while_playing() {
+        let mut game = game();
+        game.set_status("a status");
+        let lines = drawn(&game);
+
+        assert!(shows(&lines, &["A.XB", "....", "R..."]));
+        assert!(shows(&lines, &["a status"]));
+    }
+
+    #[test]
+    fn cycles_through_and_labels_fifteen_robots() {
+        let state =
+            State::from_str_grid("RRRRRRRR\nRRRRRRR.\n........\nA.....XB").expect("a valid grid");
+        let mut game = Game::new(state).expect("a solvable board");
+        assert_eq!(game.state().num_robots(), 15);
+        let selected_line = |game: &Game| {
+            drawn(game)
+                .into_iter()
+                .find(|line| line.starts_with("selected: "))
+                .expect("a selection line")
+        };
+
+        let mut labels = Vec::new();
+        for _ in 0..16 {
+            labels.push(selected_line(&game));
+            game.select_next_character();
+        }
+        assert_eq!(game.selected, Selection::Astro);
+        assert_eq!(labels.iter().unique().count(), 16);
+        assert_eq!(labels[10], "selected: robot 10/15");
+        assert_eq!(labels[15], "selected: robot 15/15");
+
+        //going back wraps around to the last robot
+        game.select_prev_character();
+        assert_eq!(game.selected, Selection::Robot(14));
+        assert_eq!(selected_line(&game), "selected: robot 15/15");
+        assert!(shows(
+            &drawn(&game),
+            &["RRRRRRRR", "RRRRRRR.", "........", "A.....XB"]
+        ));
+    }
+
+    #[test]
+    fn draws_in_the_top_left_without_a_terminal() {
+        set_colors(false);
+        let mut output = Vec::new();
+        game().draw_sized(&mut output, None).expect("drawn");
+        let rows = screen(&output);
+
+        assert_eq!(rows[..3], ["A.XB", "....", "R..."]);
+    }
+
+    #[test]
+    fn asks_for_a_bigger_terminal_than_the_board() {
+        set_colors(false);
+        let mut output = Vec::new();
+        game().draw_sized(&mut output, Some((3, 2))).expect("drawn");
+        let prompt = screen(&output).join(" ");
+
+        assert!(prompt.starts_with("Please resize your terminal to at least"));
+        assert!(!prompt.contains("A.XB"));
+    }
+
+    #[test]
+    fn draws_the_walkthrough() {
+        let mut game = game();
+        game.toggle_mode();
+        let lines = drawn(&game);
+
+        assert!(shows(
+            &lines,
+            &[
+                "STARTING POSITION",
+                "astro: (0, 0) => (2, 0)",
+                "",
+                "A.XB",
+                "....",
+                "R..."
+            ]
+        ));
+    }
+
+    #[test]
+    fn draws_the_solved_board() {
+        let lines = drawn(&solved());
+
+        assert!(shows(&lines, &["..AB", "....", "R..."]));
+        assert!(shows(&lines, &["moves: 1 (par 1, 100% efficient)"]));
+        assert!(shows(&lines, &["r: restart"]));
+    }
+
+    #[test]
+    fn draws_the_review_side_by_side() {
+        let mut game = solved();
+        game.toggle_review();
+        let lines = drawn(&game);
+
+        assert!(shows(&lines, &["YOUR MOVES (1)             OPTIMAL (1)"]));
+        assert!(shows(&lines, &["A.XB                       A.XB"]));
+    }
+
+    #[test]
+    fn draws_the_intro() {
+        set_colors(false);
+        let mut output = Vec::new();
+        game()
+            .draw_intro_sized(&mut output, Some(SIZE))
+            .expect("drawn");
+        let lines = trimmed(&output);
+
+        assert!(shows(&lines, &["ASTRO AND ROBOTS", "", "board: 3x4"]));
+        assert!(shows(&lines, &["press any key to start"]));
+    }
+
+    fn default_game() -> Game {
+        let state =
+            State::from_str_grid("R.R.R\n.....\n..X..\n....R\n.A...").expect("a valid grid");
+        Game::new(state).expect("a solvable board")
+    }
+
+    fn make_move(game: &mut Game, selection: Selection, direction: Direction) {
+        game.selected = selection;
+        let Action::Movement(MovementAttempt::Success(pos)) = game.move_toward(direction) else {
+            panic!("{selection:?} can't move {direction:?}");
+        };
+        game.move_selection_to(pos);
+    }
+
+    /// the second and third robots slide left, as the default board's solution starts
+    fn two_moves_in() -> Game {
+        let mut game = default_game();
+        make_move(&mut game, Selection::Robot(1), Direction::Left);
+        make_move(&mut game, Selection::Robot(2), Direction::Left);
+        game
+    }
+
+    #[test]
+    fn robots_without_moves_left_can_not_be_pushed() {
+        let state = State::from_str_grid("B...R.A.B\n......X..\n......B..")
+            .expect("a valid grid")
+            .with_collision_rule(CollisionRule::Push);
+        let mut game = Game::new(state)
+            .and_then(|game| game.with_robot_move_limit(1))
+            .expect("solvable within the limit");
+        make_move(&mut game, Selection::Robot(0), Direction::Right);
+        assert_eq!(game.moves_left(Selection::Robot(0)), Some(0));
+
+        //the astro would push the robot back to the left
+        let before = game.state().clone();
+        make_move(&mut game, Selection::Astro, Direction::Left);
+        assert_eq!(game.state(), &before);
+        assert_eq!(game.status.as_deref(), Some("robot 1 has no moves left"));
+    }
+
+    #[test]
+    fn undo_all_keeps_the_moves_to_redo() {
+        let mut game = two_moves_in();
+        let played = game.state().clone();
+
+        game.undo_all();
+        assert_eq!(game.num_moves(), 0);
+        assert_eq!(game.state(), &game.solution()[0]);
+
+        game.redo_all();
+        assert_eq!(game.num_moves(), 2);
+        assert_eq!(game.state(), &played);
+    }
+
+    #[test]
+    fn undo_keeps_the_walkthrough_in_step_on_the_optimal_line() {
+        let mut game = two_moves_in();
+        //peeking further ahead in the walkthrough
+        for _ in 0..5 {
+            game.walkthrough_next();
+        }
+
+        game.undo();
+        assert_eq!(game.walkthrough().current_step, 1);
+        game.undo();
+        assert_eq!(game.walkthrough().current_step, 0);
+    }
+
+    #[test]
+    fn undo_leaves_the_walkthrough_alone_off_the_optimal_line() {
+        let mut game = default_game();
+        make_move(&mut game, Selection::Robot(0), Direction::Right);
+        make_move(&mut game, Selection::Robot(2), Direction::Left);
+        game.walkthrough_next();
+
+        game.undo();
+        assert_eq!(game.walkthrough().current_step, 1);
+    }
+
+    #[test]
+    fn undo_limit_drops_nothing_up_to_the_limit() {
+        let mut game = default_game().with_undo_limit(2).expect("no move limits");
+        make_move(&mut game, Selection::Robot(1), Direction::Left);
+        make_move(&mut game, Selection::Robot(2), Direction::Left);
+
+        game.undo();
+        game.undo();
+        assert_eq!(game.num_moves(), 0);
+        assert_eq!(game.state(), &game.solution()[0]);
+    }
+
+    #[test]
+    fn undo_limit_stops_undoing_past_the_dropped_moves() {
+        let mut game = default_game().with_undo_limit(1).expect("no move limits");
+        make_move(&mut game, Selection::Robot(1), Direction::Left);
+        make_move(&mut game, Selection::Robot(2), Direction::Left);
+        make_move(&mut game, Selection::Robot(1), Direction::Down);
+
+        game.undo();
+        assert_eq!(game.num_moves(), 2);
+        game.undo();
+        assert_eq!(game.num_moves(), 2);
+        assert_eq!(
+            game.status.as_deref(),
+            Some("no more history to undo (--undo-limit 1)")
+        );
+
+        //the initial state is never dropped
+        game.restart();
+        assert_eq!(game.num_moves(), 0);
+        assert_eq!(game.state(), &game.solution()[0]);
+    }
+
+    #[test]
+    fn redo_restores_the_selection_and_mode() {
+        let mut game = default_game();
+        make_move(&mut game, Selection::Robot(1), Direction::Left);
+        game.select_next_character();
+        let before = (game.state().clone(), game.selected, game.mode());
+
+        game.undo();
+        //the selection redo brings back is the one the move was undone with
+        game.select_next_character();
+        game.redo();
+        assert_eq!((game.state().clone(), game.selected, game.mode()), before);
+    }
+
+    #[test]
+    fn restart_forgets_the_moves_to_redo() {
+        let mut game = two_moves_in();
+
+        game.restart();
+        game.redo_all();
+        assert_eq!(game.num_moves(), 0);
+        assert_eq!(game.state(), &game.solution()[0]);
+    }
+
+    /// a board that the astro traps itself on by moving up, against the top robots
+    fn self_trapping() -> Game {
+        let state = State::from_str_grid(".RR.\nR...\n.X..\nR.A.").expect("a valid grid");
+        Game::new(state).expect("a solvable board")
+    }
+
+    #[test]
+    fn warns_when_a_move_leaves_no_solution() {
+        let mut game = self_trapping().with_unsolvable_warning();
+        make_move(&mut game, Selection::Astro, Direction::Up);
+
+        assert_eq!(
+            game.status.as_deref(),
+            Some("no solution from here, undo or restart")
+        );
+    }
+
+    #[test]
+    fn only_warns_about_no_solution_when_asked_to() {
+        let mut game = self_trapping();
+        make_move(&mut game, Selection::Astro, Direction::Up);
+
+        assert_eq!(game.status, None);
+    }
+
+    #[test]
+    fn selection_keys_wrap_past_the_ends() {
+        let last = Selection::Robot(2);
+        assert_eq!(keys(Selection::Astro, last, true), [Key::Char('z')]);
+        assert_eq!(keys(last, Selection::Astro, true), [Key::Char('x')]);
+        assert_eq!(keys(last, last, true), []);
+    }
+
+    #[test]
+    fn selection_keys_without_wrapping_stay_between_the_ends() {
+        let last = Selection::Robot(2);
+        assert_eq!(keys(Selection::Astro, last, false), [Key::Char('x'); 3]);
+        assert_eq!(keys(last, Selection::Astro, false), [Key::Char('z'); 3]);
+        assert_eq!(
+            keys(Selection::Robot(0), Selection::Robot(1), false),
+            [Key::Char('x')]
+        );
+        assert_eq!(keys(Selection::Astro, Selection::Astro, false), []);
+    }
 }
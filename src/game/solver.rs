@@ -1,47 +1,842 @@
-use super::{Direction, State};
-use crate::state::{MovementAttempt, Selection};
+use super::State;
+use crate::state::{
+    CollisionRule, GoalOccupant, MovementAttempt, Pos, PosChange, Selection, StopRule, Tile,
+    WinCondition,
+};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
 use itertools::Itertools;
-use pathfinding::prelude::bfs;
+use pathfinding::prelude::{bfs, bfs_reach, dijkstra};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display};
+use std::iter;
+use std::time::Instant;
+
+/// the result of a search that may give up before finishing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveOutcome {
+    Solved(Vec<State>),
+    Unsolved,
+    //the number of states that were explored before giving up
+    Aborted(usize),
+}
+
+/// every shortest solution of a board, up to some maximum number of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OptimalSolutions {
+    pub solutions: Vec<Vec<State>>,
+    //whether there were more solutions than the maximum
+    pub truncated: bool,
+}
+
+/// how much moving each kind of piece counts toward a solution's cost
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveCosts {
+    pub robot: u32,
+    pub astro: u32,
+}
+
+impl Default for MoveCosts {
+    //robots are harder to keep track of, so moving them counts twice
+    fn default() -> Self {
+        MoveCosts { robot: 2, astro: 1 }
+    }
+}
+
+impl MoveCosts {
+    /// the total cost of the moves that lead from each state in `path` to the next
+    pub fn path_cost(&self, path: &[State]) -> Result<u32> {
+        iter::zip(path, State::pos_changes(path))
+            .map(|(state, change)| {
+                let change = change?;
+                match state.selection_at(change.0) {
+                    Some(Selection::Astro | Selection::Partner) => Ok(self.astro),
+                    Some(Selection::Robot(_)) => Ok(self.robot),
+                    None => Err(eyre!("no piece to move at {}", change.0)),
+                }
+            })
+            .sum()
+    }
+}
+
+/// what a solution is optimal in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolverKind {
+    Shortest,
+    Weighted(MoveCosts),
+    //however many astro moves it takes
+    MinRobotMoves,
+}
+
+impl SolverKind {
+    pub fn solve(self, state: &State) -> Option<Vec<State>> {
+        match self {
+            SolverKind::Shortest => state.solve_from_here(),
+            SolverKind::Weighted(MoveCosts { robot, astro }) => state
+                .solve_weighted(robot, astro)
+                .map(|(solution, _)| solution),
+            SolverKind::MinRobotMoves => {
+                state.solve_min_robot_moves().map(|(solution, _)| solution)
+            }
+        }
+    }
+}
+
+impl Display for SolverKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SolverKind::Shortest => write!(f, "fewest moves"),
+            SolverKind::Weighted(MoveCosts { robot, astro }) => {
+                write!(f, "cheapest (robot {robot}, astro {astro})")
+            }
+            SolverKind::MinRobotMoves => write!(f, "fewest robot moves"),
+        }
+    }
+}
 
 impl State {
     fn successor_of(&self, selection: Selection) -> impl IntoIterator<Item = State> + '_ {
         let current_pos = self.pos_of(selection);
-        let directions = [
-            Direction::Up,
-            Direction::Down,
-            Direction::Left,
-            Direction::Right,
-        ];
 
-        directions.into_iter().filter_map(move |direction| {
-            let attempt = self.move_toward(current_pos, direction);
+        self.movement()
+            .directions()
+            .iter()
+            .filter_map(move |&direction| {
+                let attempt = self.move_toward(current_pos, direction);
 
-            match attempt {
-                MovementAttempt::Success(new_pos) => {
-                    let mut new_state = self.clone();
-                    *new_state.pos_of_mut(selection) = new_pos;
-                    Some(new_state)
+                match attempt {
+                    MovementAttempt::Success(new_pos) => {
+                        let mut new_state = self.clone();
+                        //a push moves a robot as well
+                        if self.collision_rule() == CollisionRule::Push {
+                            let moved = new_state.try_move(selection, new_pos);
+                            debug_assert_eq!(moved, Ok(()));
+                            return Some(new_state);
+                        }
+
+                        //checked only in debug builds, since this is what the solver spends its time on
+                        debug_assert_eq!(self.check_place(selection, new_pos), Ok(()));
+                        new_state.set_pos(selection, new_pos);
+                        Some(new_state)
+                    }
+                    MovementAttempt::Failure => None,
                 }
-                MovementAttempt::Failure => None,
-            }
-        })
+            })
     }
 
+    /// every state one move away, ordered by where the pieces are rather than by which one moved,
+    /// so that which of several optimal solutions `bfs` finds doesn't depend on how they're generated.
     fn all_successors(&self) -> impl IntoIterator<Item = State> {
-        let mut selections = Vec::with_capacity(self.num_robots() + 1);
+        //collecting is required here, otherwise a hidden lifetime is introduced.
+        let mut successors = self
+            .selections()
+            .flat_map(|selection| self.successor_of(selection))
+            .collect_vec();
+        successors.sort_unstable_by(|a, b| a.canonical_cmp(b));
+        successors
+    }
 
-        selections.push(Selection::Astro);
-        let robots = (0..self.num_robots()).map(Selection::Robot);
-        selections.extend(robots);
+    /// compares the astro first, then each robot in order
+    fn canonical_cmp(&self, other: &State) -> Ordering {
+        (self.astro, self.partner(), self.robots()).cmp(&(
+            other.astro,
+            other.partner(),
+            other.robots(),
+        ))
+    }
+
+    /// like `all_successors`, but only for the pieces with moves left in `remaining`,
+    /// which has an entry for each piece in the order of `selections`. `None` is unlimited.
+    /// a robot that gets pushed uses up one of its moves as well, so it can't be pushed without any left.
+    fn limited_successors(&self, remaining: &[Option<usize>]) -> Vec<(State, Vec<Option<usize>>)> {
+        let selections = self.selections().collect_vec();
 
-        //collecting is required here, otherwise a hidden lifetime is introduced.
         selections
+            .iter()
+            .zip(remaining)
+            .enumerate()
+            .filter(|(_, (_, moves_left))| moves_left.is_none_or(|moves_left| moves_left > 0))
+            .flat_map(|(i, (&selection, _))| {
+                let selections = &selections;
+                self.successor_of(selection)
+                    .into_iter()
+                    .filter_map(move |state| {
+                        let mut remaining = remaining.to_vec();
+                        remaining[i] = remaining[i].map(|moves_left| moves_left - 1);
+
+                        let pushed =
+                            (0..self.num_robots())
+                                .map(Selection::Robot)
+                                .filter(|&robot| {
+                                    robot != selection && state.pos_of(robot) != self.pos_of(robot)
+                                });
+                        for robot in pushed {
+                            let j = selections
+                                .iter()
+                                .position(|&s| s == robot)
+                                .expect("a piece on the board");
+                            remaining[j] = match remaining[j] {
+                                Some(0) => return None,
+                                moves_left => moves_left.map(|moves_left| moves_left - 1),
+                            };
+                        }
+                        Some((state, remaining))
+                    })
+            })
+            .collect_vec()
+    }
+
+    fn weighted_successors(&self, robot_cost: u32, astro_cost: u32) -> Vec<(State, u32)> {
+        let astro = [Selection::Astro, Selection::Partner]
             .into_iter()
+            .take(self.astros().count())
             .flat_map(|selection| self.successor_of(selection))
-            .collect_vec()
+            .map(|state| (state, astro_cost));
+        let robots = (0..self.num_robots())
+            .map(Selection::Robot)
+            .flat_map(|selection| self.successor_of(selection))
+            .map(|state| (state, robot_cost));
+
+        astro.chain(robots).collect_vec()
     }
 
     pub fn solve_from_here(&self) -> Option<Vec<Self>> {
-        bfs(self, State::all_successors, State::is_at_goal)
+        let solution = bfs(self, State::all_successors, State::is_at_goal);
+        //trace rather than debug, since generation solves thousands of candidates
+        match &solution {
+            Some(solution) => log::trace!("solved in {} moves", solution.len() - 1),
+            None => log::trace!("no solution"),
+        }
+        solution
+    }
+
+    /// like `solve_from_here`, but each piece in `limits` can be moved at most as many times as it maps to.
+    /// the pieces that aren't in `limits` can be moved any number of times.
+    pub fn solve_with_move_limits(&self, limits: &HashMap<Selection, usize>) -> Option<Vec<Self>> {
+        let remaining = self
+            .selections()
+            .map(|selection| limits.get(&selection).copied())
+            .collect_vec();
+
+        let path = bfs(
+            &(self.clone(), remaining),
+            |(state, remaining)| state.limited_successors(remaining),
+            |(state, _)| state.is_at_goal(),
+        )?;
+        Some(path.into_iter().map(|(state, _)| state).collect())
+    }
+
+    /// like `solve_from_here`, but without ever moving a robot, so only the astros move
+    pub fn solve_astro_only(&self) -> Option<Vec<Self>> {
+        self.solve_with_move_limits(&robot_move_limits(self.num_robots(), 0))
+    }
+
+    /// whether the state is unsolvable in a way that's cheap to tell without searching:
+    /// no piece can move at all, or no piece can ever come to rest on any of the goals.
+    /// `false` doesn't mean that the state is solvable
+    pub fn has_trivial_dead_end(&self) -> bool {
+        !self.is_at_goal() && (self.is_frozen() || self.goals_out_of_reach())
+    }
+
+    fn is_frozen(&self) -> bool {
+        self.selections()
+            .all(|selection| self.successor_of(selection).into_iter().next().is_none())
+    }
+
+    fn goals_walled_in(&self) -> bool {
+        self.goal_positions().iter().all(|&goal| {
+            self.goal_approaches(goal)
+                .all(|pos| pos.is_none_or(|pos| self.tile_at(pos) == Tile::Blocker))
+        })
+    }
+
+    /// whether no piece can ever stop on any goal: every way onto one either starts from a blocker
+    /// or past the edge, or would slide on over the edge, with nothing there to stop the piece on it.
+    /// that's the case for a goal in a corner, unless pieces stop on goals or move diagonally
+    fn goals_out_of_reach(&self) -> bool {
+        let stops_on_goals = self.stop_rule() == StopRule::OnGoal;
+        self.goal_positions().iter().all(|&goal| {
+            self.movement().directions().iter().all(|&direction| {
+                let from = goal.step(direction.opposite(), self.dims());
+                let beyond = goal.step(direction, self.dims());
+                from.is_none_or(|from| self.tile_at(from) == Tile::Blocker)
+                    || (beyond.is_none() && !stops_on_goals)
+            })
+        })
+    }
+
+    /// the tiles next to `goal` that a piece could slide onto it from, `None` past the edge of the grid
+    fn goal_approaches(&self, goal: Pos) -> impl Iterator<Item = Option<Pos>> + '_ {
+        self.movement()
+            .directions()
+            .iter()
+            .map(move |&direction| goal.step(direction, self.dims()))
+    }
+
+    /// why the goal can't be reached from here, in a sentence, or `None` if it can.
+    /// looks at every position the pieces can get into, which makes it as slow as
+    /// `solve_from_here` on a board that can't be solved
+    pub fn explain_unsolvable(&self) -> Option<String> {
+        if self.is_at_goal() {
+            return None;
+        }
+        if self.is_frozen() {
+            return Some("no piece can move".to_string());
+        }
+        if self.goals_walled_in() {
+            return Some(
+                "every goal is walled in by blockers and the edges of the grid".to_string(),
+            );
+        }
+        if self.goals_out_of_reach() {
+            return Some(
+                "no piece can ever stop on a goal, since nothing past one would stop it there"
+                    .to_string(),
+            );
+        }
+
+        let reachable = bfs_reach(self.clone(), State::all_successors).collect_vec();
+        if reachable.iter().any(State::is_at_goal) {
+            return None;
+        }
+
+        //a goal can't be slid onto if the tiles it's approached from are always taken
+        let sealed_by = |goal: Pos| -> Option<Vec<Pos>> {
+            let mut robots = Vec::new();
+            for pos in self.goal_approaches(goal).flatten() {
+                if self.tile_at(pos) == Tile::Blocker {
+                    continue;
+                }
+                if !reachable
+                    .iter()
+                    .all(|state| state.tile_at(pos) == Tile::Robot)
+                {
+                    return None;
+                }
+                robots.push(pos);
+            }
+            Some(robots)
+        };
+        let sealed = self
+            .goal_positions()
+            .iter()
+            .map(|&goal| sealed_by(goal).map(|robots| (goal, robots)))
+            .collect::<Option<Vec<_>>>();
+        //some of the goals may be walled in, but not all of them, or that would've been caught above
+        let sealed_by_robots = sealed
+            .iter()
+            .flatten()
+            .find(|(_, robots)| !robots.is_empty());
+        if let Some((goal, robots)) = sealed_by_robots {
+            let robots = robots.iter().join(" and ");
+            return Some(format!(
+                "the goal at {goal} is sealed off by robots that never leave {robots}"
+            ));
+        }
+
+        let goals = self.goal_positions();
+        let reaches_goal = |selection: Selection| {
+            reachable
+                .iter()
+                .any(|state| goals.contains(&state.pos_of(selection)))
+        };
+        let required = match self.win_condition() {
+            WinCondition::AstroOnly => self.selections().take(self.astros().count()).collect_vec(),
+            WinCondition::AllPieces => self.selections().collect_vec(),
+            WinCondition::Occupant(GoalOccupant::Robot(n)) => vec![Selection::Robot(n)],
+            WinCondition::Occupant(GoalOccupant::AnyRobot) => {
+                if !(0..self.num_robots()).any(|n| reaches_goal(Selection::Robot(n))) {
+                    return Some("no robot can ever get onto a goal".to_string());
+                }
+                Vec::new()
+            }
+        };
+        for selection in required {
+            if !reaches_goal(selection) {
+                let tiles = reachable
+                    .iter()
+                    .map(|state| state.pos_of(selection))
+                    .unique()
+                    .count();
+                let tiles = match tiles {
+                    1 => "where it is now".to_string(),
+                    _ => format!("on {tiles} tiles, none of them a goal"),
+                };
+                return Some(format!(
+                    "the {} can only ever come to rest {tiles}",
+                    piece_name(selection)
+                ));
+            }
+        }
+
+        Some("each piece can get onto a goal, but never all of them at once".to_string())
+    }
+
+    /// every tile the astro can come to rest on by moving only itself, any number of times,
+    /// including the one it's on. the robots stay where they are, so under `CollisionRule::Push`
+    /// the astro can't make the moves that would push one
+    pub fn astro_reachable_positions(&self) -> HashSet<Pos> {
+        //collected for the same reason as in `all_successors`
+        bfs_reach(self.clone(), |state| {
+            state
+                .successor_of(Selection::Astro)
+                .into_iter()
+                .filter(|successor| successor.robots() == state.robots())
+                .collect_vec()
+        })
+        .map(|state| state.astro)
+        .collect()
+    }
+
+    /// every tile a piece can stop on in a single move, along with the piece that would stop there.
+    /// a tile can be listed once for each piece that reaches it.
+    pub fn reachable_tiles(&self) -> Vec<(Selection, Pos)> {
+        //a push moves more than one piece, so the mover can't be told from the successor alone
+        self.selections()
+            .flat_map(|selection| {
+                self.successor_of(selection)
+                    .into_iter()
+                    .map(move |successor| (selection, successor.pos_of(selection)))
+            })
+            .collect()
+    }
+
+    /// like `solve_from_here`, but gives up after exploring `max_nodes` states,
+    /// so that huge boards can't use up all the memory.
+    pub fn solve_bounded(&self, max_nodes: usize) -> SolveOutcome {
+        self.solve_limited(max_nodes, None)
+    }
+
+    /// like `solve_bounded`, but also gives up once `deadline` has passed.
+    pub fn solve_before(&self, max_nodes: usize, deadline: Instant) -> SolveOutcome {
+        self.solve_limited(max_nodes, Some(deadline))
+    }
+
+    fn solve_limited(&self, max_nodes: usize, deadline: Option<Instant>) -> SolveOutcome {
+        //each state is stored along with the index of the state it was reached from
+        let mut nodes = vec![(self.clone(), usize::MAX)];
+        let mut seen = HashSet::from([self.clone()]);
+
+        let path_to = |nodes: &[(State, usize)], mut i: usize| {
+            let mut path = Vec::new();
+            while let Some((state, parent)) = nodes.get(i) {
+                path.push(state.clone());
+                i = *parent;
+            }
+            path.reverse();
+            path
+        };
+
+        if self.is_at_goal() {
+            return SolveOutcome::Solved(path_to(&nodes, 0));
+        }
+
+        let mut explored = 0;
+        while explored < nodes.len() {
+            let timed_out = deadline.is_some_and(|deadline| Instant::now() >= deadline);
+            if explored >= max_nodes || timed_out {
+                return SolveOutcome::Aborted(explored);
+            }
+
+            for successor in nodes[explored].0.all_successors() {
+                if !seen.insert(successor.clone()) {
+                    continue;
+                }
+
+                let is_at_goal = successor.is_at_goal();
+                nodes.push((successor, explored));
+                if is_at_goal {
+                    return SolveOutcome::Solved(path_to(&nodes, nodes.len() - 1));
+                }
+            }
+
+            explored += 1;
+        }
+
+        SolveOutcome::Unsolved
+    }
+
+    /// every solution as short as the one `solve_from_here` finds, but at most `max_solutions` of them,
+    /// since branchy boards can have a huge number of equally short solutions.
+    pub fn all_optimal_solutions(&self, max_solutions: usize) -> OptimalSolutions {
+        //every state reached so far, along with all the states of the previous depth it's reached from
+        let mut predecessors: HashMap<State, Vec<State>> =
+            HashMap::from([(self.clone(), Vec::new())]);
+        let mut layer = vec![self.clone()];
+        let mut goals = Vec::new();
+        if self.is_at_goal() {
+            goals.push(self.clone());
+        }
+
+        while goals.is_empty() && !layer.is_empty() {
+            //kept in a vec as well, so that the solutions always come in the same order
+            let mut next_layer = Vec::new();
+            let mut next_predecessors: HashMap<State, Vec<State>> = HashMap::new();
+
+            for state in &layer {
+                for successor in state.all_successors() {
+                    if predecessors.contains_key(&successor) {
+                        continue;
+                    }
+                    let preds = next_predecessors.entry(successor.clone()).or_default();
+                    if preds.is_empty() {
+                        next_layer.push(successor);
+                    }
+                    preds.push(state.clone());
+                }
+            }
+
+            goals = next_layer
+                .iter()
+                .filter(|state| state.is_at_goal())
+                .cloned()
+                .collect();
+            predecessors.extend(next_predecessors);
+            layer = next_layer;
+        }
+
+        //walks back from each goal, one more solution than needed tells whether there are too many
+        let mut solutions = Vec::new();
+        let mut stack = goals.into_iter().map(|goal| vec![goal]).collect_vec();
+        stack.reverse();
+        while let Some(path) = stack.pop() {
+            if solutions.len() > max_solutions {
+                break;
+            }
+
+            let last = path.last().expect("paths are never empty");
+            let preds = &predecessors[last];
+            if preds.is_empty() {
+                let mut solution = path;
+                solution.reverse();
+                solutions.push(solution);
+                continue;
+            }
+
+            for pred in preds.iter().rev() {
+                let mut longer = path.clone();
+                longer.push(pred.clone());
+                stack.push(longer);
+            }
+        }
+
+        let truncated = solutions.len() > max_solutions;
+        solutions.truncate(max_solutions);
+        OptimalSolutions {
+            solutions,
+            truncated,
+        }
+    }
+
+    /// whether the goal can be reached at all, without building the path to it
+    pub fn is_solvable(&self) -> bool {
+        bfs_reach(self.clone(), State::all_successors).any(|state| state.is_at_goal())
+    }
+
+    /// whether sliding the piece at `from` to `to` leaves exactly one move fewer to the goal.
+    /// an illegal move, or one from a board that can't be solved, is never optimal.
+    /// this solves both this board and the one the move leads to, so it's as slow as two
+    /// `solve_from_here`s. `Game` remembers its distances, and is the one to ask repeatedly.
+    pub fn is_optimal_move(&self, from: Pos, to: Pos) -> bool {
+        let Ok(states) = self.replay(&[PosChange(from, to)]) else {
+            return false;
+        };
+        let Some(remaining) = self.solve_from_here().map(|solution| solution.len() - 1) else {
+            return false;
+        };
+
+        let next = states.last().expect("replay starts with this state");
+        next.solve_from_here()
+            .is_some_and(|solution| solution.len() == remaining)
+    }
+
+    /// like `solve_from_here`, but minimizes the total cost of the moves rather than their number.
+    /// returns the solution along with its cost.
+    pub fn solve_weighted(&self, robot_cost: u32, astro_cost: u32) -> Option<(Vec<Self>, u32)> {
+        let successors = |state: &State| state.weighted_successors(robot_cost, astro_cost);
+        dijkstra(self, successors, State::is_at_goal)
+    }
+
+    /// finds the solution that moves robots the fewest times, however many astro moves it takes.
+    /// returns the solution along with its number of robot moves.
+    pub fn solve_min_robot_moves(&self) -> Option<(Vec<Self>, u32)> {
+        self.solve_weighted(1, 0)
+    }
+}
+
+/// what `explain_unsolvable` calls `selection`
+fn piece_name(selection: Selection) -> String {
+    match selection {
+        Selection::Astro => "astro".to_string(),
+        Selection::Partner => "second astro".to_string(),
+        Selection::Robot(n) => format!("robot {}", n + 1),
+    }
+}
+
+/// move limits that let each of `num_robots` robots move `moves` times, and the astro any number of times
+pub fn robot_move_limits(num_robots: usize, moves: usize) -> HashMap<Selection, usize> {
+    (0..num_robots)
+        .map(|n| (Selection::Robot(n), moves))
+        .collect()
+}
+
+/// a rough measure of how hard a board is, given its optimal solution.
+///
+/// longer solutions, more robots to keep track of,
+/// and more possible moves at each step all make a board harder.
+pub fn difficulty_score(solution: &[State]) -> u32 {
+    let Some(initial_state) = solution.first() else {
+        return 0;
+    };
+
+    let moves = solution.len() - 1;
+    let num_robots = initial_state.num_robots();
+
+    //the goal state is not counted, since no move is made from it
+    let branching: usize = solution[..moves]
+        .iter()
+        .map(|state| state.all_successors().into_iter().count())
+        .sum();
+    let avg_branching = branching.checked_div(moves).unwrap_or(0);
+
+    let score = moves * 4 + num_robots * 2 + avg_branching;
+    u32::try_from(score).unwrap_or(u32::MAX)
+}
+
+/// buckets a `difficulty_score` into a rating from 1 to 5.
+///
+/// the thresholds roughly split randomly-generated 5x5 to 7x7 boards into even groups.
+pub fn difficulty_stars(score: u32) -> u8 {
+    match score {
+        ..=25 => 1,
+        26..=30 => 2,
+        31..=37 => 3,
+        38..=47 => 4,
+        _ => 5,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{Direction, Movement};
+    use nanorand::{Rng, WyRand};
+
+    fn pushing(grid: &str) -> State {
+        State::from_str_grid(grid)
+            .expect("a valid grid")
+            .with_collision_rule(CollisionRule::Push)
+    }
+
+    fn grid(grid: &str) -> State {
+        State::from_str_grid(grid).expect("a valid grid")
+    }
+
+    #[test]
+    fn frozen_boards_are_trivial_dead_ends() {
+        //every piece is against another one or would slide off the grid
+        let state = grid("AR.\nR..\n..X");
+        assert!(state.is_frozen());
+        assert!(state.has_trivial_dead_end());
+        assert_eq!(
+            state.explain_unsolvable().as_deref(),
+            Some("no piece can move")
+        );
+    }
+
+    #[test]
+    fn walled_in_goals_are_trivial_dead_ends() {
+        let state = grid(".B.\nBXB\n.BA");
+        assert!(state.goals_walled_in());
+        assert!(state.has_trivial_dead_end());
+    }
+
+    #[test]
+    fn corner_goals_are_trivial_dead_ends_unless_pieces_stop_on_them() {
+        let state = grid("X..A\n....\nBR..\n...B");
+        assert!(!state.goals_walled_in());
+        assert!(state.has_trivial_dead_end());
+        assert_eq!(
+            state.explain_unsolvable().as_deref(),
+            Some("no piece can ever stop on a goal, since nothing past one would stop it there")
+        );
+
+        let state = state.with_stop_rule(StopRule::OnGoal);
+        assert!(!state.has_trivial_dead_end());
+        assert!(state.solve_from_here().is_some());
+    }
+
+    #[test]
+    fn trivial_dead_ends_are_never_solvable() {
+        let mut rng = WyRand::new_seed(0);
+        let positions = (0..4).cartesian_product(0..4).map(Pos::from).collect_vec();
+        let mut dead_ends = 0;
+        for _ in 0..500 {
+            let mut positions = positions.clone();
+            rng.shuffle(&mut positions);
+            let num_robots = rng.generate_range(0..6_usize);
+            let robots = positions[2..2 + num_robots].to_vec();
+            let state = State::new(positions[0], positions[1], robots, 4, 4).expect("apart");
+
+            if state.has_trivial_dead_end() {
+                dead_ends += 1;
+                assert_eq!(state.solve_from_here(), None, "{}", state.to_str_grid());
+            }
+        }
+        assert!(dead_ends > 0);
+    }
+
+    #[test]
+    fn sealed_goals_skip_the_walled_in_ones() {
+        //the first goal is walled in, and the robots beside the second one can never move,
+        //since they can't stop on it
+        let state = grid("XBRXRB\nB.BBB.\nA....B").with_stop_rule(StopRule::RobotsAvoidGoal);
+
+        assert_eq!(
+            state.explain_unsolvable().as_deref(),
+            Some("the goal at (3, 0) is sealed off by robots that never leave (2, 0) and (4, 0)")
+        );
+    }
+
+    /// the board that `--default` plays
+    const DEFAULT_BOARD: &str = "R.R.R\n.....\n..X..\n....R\n.A...";
+
+    #[test]
+    fn optimal_moves_start_the_default_solution() {
+        let state = State::from_str_grid(DEFAULT_BOARD).expect("a valid grid");
+
+        //the second robot sliding left, as the solution starts
+        assert!(state.is_optimal_move(Pos { x: 2, y: 0 }, Pos { x: 1, y: 0 }));
+        //the first robot sliding right instead, after which the goal is further away
+        assert!(!state.is_optimal_move(Pos { x: 0, y: 0 }, Pos { x: 1, y: 0 }));
+        //not a move that can be made at all
+        assert!(!state.is_optimal_move(Pos { x: 1, y: 4 }, Pos { x: 3, y: 2 }));
+    }
+
+    #[test]
+    fn astro_reaches_the_default_goal_only_after_the_robots_move() {
+        let state = State::from_str_grid(DEFAULT_BOARD).expect("a valid grid");
+        let goal = state.goal_positions()[0];
+        assert!(!state.astro_reachable_positions().contains(&goal));
+
+        //the astro finishes the solution on its own once the last robot has moved
+        let solution = state
+            .solve_from_here()
+            .expect("the default board is solvable");
+        let robots_done = solution.last().unwrap().robots();
+        let after_robots = solution
+            .iter()
+            .find(|state| state.robots() == robots_done)
+            .unwrap();
+        assert_ne!(after_robots.robots(), state.robots());
+        assert!(after_robots.astro_reachable_positions().contains(&goal));
+    }
+
+    #[test]
+    fn astro_reachable_positions_never_push() {
+        let state = pushing("A.R.X.B");
+        assert_eq!(
+            state.astro_reachable_positions(),
+            HashSet::from([Pos { x: 0, y: 0 }])
+        );
+    }
+
+    #[test]
+    fn weighted_solutions_trade_robot_moves_for_astro_moves() {
+        let state =
+            State::from_str_grid(".R...\n.R..A\n...X.\n.R..R\n..R..").expect("a valid grid");
+
+        let shortest = state.solve_from_here().expect("a solution");
+        assert_eq!(shortest.len() - 1, 6);
+        //one more move, but with fewer of them robot moves
+        let (cheapest, cost) = state.solve_weighted(2, 1).expect("a solution");
+        assert_eq!((cheapest.len() - 1, cost), (7, 9));
+    }
+
+    #[test]
+    fn min_robot_moves_differ_from_the_shortest_solution() {
+        let state =
+            State::from_str_grid(".R...\n.R..A\n...X.\n.R..R\n..R..").expect("a valid grid");
+        let robot_moves = |solution: &[State]| {
+            solution
+                .iter()
+                .tuple_windows()
+                .filter(|(before, after)| before.robots() != after.robots())
+                .count()
+        };
+
+        let shortest = state.solve_from_here().expect("a solution");
+        let (fewest, count) = state.solve_min_robot_moves().expect("a solution");
+        assert_eq!(robot_moves(&fewest), count as usize);
+        assert_eq!((robot_moves(&shortest), count), (4, 2));
+    }
+
+    /// either robot can slide over to stop the astro on the goal
+    const SYMMETRIC: &str = ".R.R.\n..X..\n..A..";
+
+    #[test]
+    fn all_optimal_solutions_finds_both_sides() {
+        let state = State::from_str_grid(SYMMETRIC).expect("a valid grid");
+
+        let optimal = state.all_optimal_solutions(10);
+        assert!(!optimal.truncated);
+        assert_eq!(optimal.solutions.len(), 2);
+        assert!(optimal.solutions.iter().all(|solution| solution.len() == 3));
+        assert_ne!(optimal.solutions[0], optimal.solutions[1]);
+    }
+
+    #[test]
+    fn all_optimal_solutions_stops_at_the_maximum() {
+        let state = State::from_str_grid(SYMMETRIC).expect("a valid grid");
+
+        let optimal = state.all_optimal_solutions(1);
+        assert!(optimal.truncated);
+        assert_eq!(optimal.solutions.len(), 1);
+    }
+
+    #[test]
+    fn reachable_tiles_name_the_pusher() {
+        //the second robot pushes the first one to the left
+        let state = pushing("B.R.R.\n......\nA....X");
+
+        let tiles = state.reachable_tiles();
+        assert!(tiles.contains(&(Selection::Robot(1), Pos { x: 2, y: 0 })));
+    }
+
+    #[test]
+    fn eight_way_solutions_cut_across_diagonally() {
+        //the astro goes round by the top row, or straight at the blocker in the corner
+        let orthogonal = grid("A..R\n....\n..X.\n..RB");
+        let eight_way = orthogonal.clone().with_movement(Movement::EightWay);
+
+        let lengths = [&orthogonal, &eight_way]
+            .map(|state| state.solve_from_here().map(|solution| solution.len() - 1));
+        assert_eq!(lengths, [Some(2), Some(1)]);
+
+        let solution = eight_way.solve_from_here().expect("a solution");
+        assert_eq!(
+            PosChange(solution[0].astro, solution[1].astro).direction(),
+            Some(Direction::DownRight)
+        );
+    }
+
+    #[test]
+    fn pushed_robots_use_up_their_moves() {
+        //the only way onto the goal pushes the robot past it
+        let state = pushing("A.R.X.B");
+
+        assert_eq!(
+            state.solve_from_here().map(|solution| solution.len()),
+            Some(2)
+        );
+        assert_eq!(state.solve_astro_only(), None);
+        let limits = robot_move_limits(state.num_robots(), 1);
+        assert_eq!(
+            state
+                .solve_with_move_limits(&limits)
+                .map(|solution| solution.len()),
+            Some(2)
+        );
     }
 }
@@ -0,0 +1,54 @@
+use crate::state::Pos;
+use std::ops::Range;
+
+/// the sub-rectangle of the grid that fits on the terminal.
+///
+/// when the grid is larger than the space available, the viewport scrolls
+/// so that the focused position stays in view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Viewport {
+    pub xs: Range<usize>,
+    pub ys: Range<usize>,
+}
+
+impl Viewport {
+    /// `available` is the (cols, rows) of terminal cells the grid can be drawn in.
+    pub fn new(dims: (usize, usize), available: (usize, usize), focus: Pos) -> Self {
+        let (rows, cols) = dims;
+        let (available_cols, available_rows) = available;
+
+        Viewport {
+            xs: visible_range(cols, available_cols, focus.x),
+            ys: visible_range(rows, available_rows, focus.y),
+        }
+    }
+
+    pub fn is_clipped_left(&self) -> bool {
+        self.xs.start > 0
+    }
+
+    pub fn is_clipped_right(&self, cols: usize) -> bool {
+        self.xs.end < cols
+    }
+
+    pub fn is_clipped_top(&self) -> bool {
+        self.ys.start > 0
+    }
+
+    pub fn is_clipped_bottom(&self, rows: usize) -> bool {
+        self.ys.end < rows
+    }
+}
+
+fn visible_range(len: usize, available: usize, focus: usize) -> Range<usize> {
+    //always show at least the focused tile, even if there's no room for it
+    let available = available.max(1);
+
+    if len <= available {
+        return 0..len;
+    }
+
+    //keep the focus as close to the middle as the grid edges allow
+    let start = focus.saturating_sub(available / 2).min(len - available);
+    start..start + available
+}
@@ -0,0 +1,48 @@
+use crate::state::State;
+use color_eyre::eyre::{eyre, Context};
+use color_eyre::Result;
+
+/// a named set of hand-picked boards, compiled into the binary.
+#[derive(Debug)]
+pub struct LevelPack {
+    pub name: &'static str,
+    //boards in the `State::from_str_grid` format, separated by blank lines
+    source: &'static str,
+}
+
+static PACKS: [LevelPack; 2] = [
+    LevelPack {
+        name: "classic",
+        source: include_str!("../levels/classic.txt"),
+    },
+    LevelPack {
+        name: "large",
+        source: include_str!("../levels/large.txt"),
+    },
+];
+
+pub fn packs() -> &'static [LevelPack] {
+    &PACKS
+}
+
+pub fn find(name: &str) -> Result<&'static LevelPack> {
+    PACKS
+        .iter()
+        .find(|pack| pack.name == name)
+        .ok_or_else(|| eyre!("no level pack named `{name}`"))
+}
+
+impl LevelPack {
+    /// the pack's boards, easiest first
+    pub fn levels(&self) -> Result<Vec<State>> {
+        self.source
+            .split("\n\n")
+            .filter(|level| !level.trim().is_empty())
+            .enumerate()
+            .map(|(i, level)| {
+                State::from_str_grid(level)
+                    .wrap_err_with(|| format!("level {} of pack `{}`", i + 1, self.name))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,5 @@
+pub mod error;
+pub mod game;
+pub mod levels;
+pub mod state;
+pub mod tutorial;
@@ -1,35 +1,255 @@
-mod game;
-mod state;
+mod bench;
+mod demo;
+mod menu;
+mod race;
+mod scan;
+mod selftest;
 
-use clap::{Arg, ArgAction, Command};
-use color_eyre::Result;
-use game::{Action, Game, Mode};
+use astro_and_robots::error::GameError;
+use astro_and_robots::game::solver::{self, MoveCosts, SolveOutcome};
+use astro_and_robots::game::{
+    self, Action, Game, GoalStyle, GridLines, Labels, Mode, MoveFeedback, RenderPriority, Summary,
+    TimeLimit, MAX_SPEED,
+};
+use astro_and_robots::state::{
+    CollisionRule, Direction, GenerationOptions, GoalOccupant, Level, LevelMeta, Movement,
+    MovementAttempt, Placement, PosChange, Selection, State, StopRule, Tile, WinCondition,
+};
+use astro_and_robots::{levels, tutorial};
+use clap::builder::PossibleValuesParser;
+use clap::error::ErrorKind;
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::config::HookBuilder;
+use color_eyre::eyre::{ensure, eyre, Context};
+use color_eyre::{Report, Result};
+use itertools::Itertools;
+use serde_json::json;
 use simple_grid::Grid;
-use state::{Direction, MovementAttempt, State, Tile};
-use std::io::{stdin, stdout};
-use termion::cursor::HideCursor;
+use std::io::{stdin, stdout, IsTerminal, Write};
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+use std::{cmp, env, fs, iter, mem, panic, process, thread};
+use termion::cursor::{self, HideCursor};
 use termion::event::Key;
 use termion::input::TermRead;
 use termion::raw::IntoRawMode;
-use termion::screen::IntoAlternateScreen;
+use termion::screen::{self, IntoAlternateScreen};
+use termion::terminal_size;
 
-fn game_loop(initial_state: State) -> Result<()> {
-    let stdin = stdin();
-    let mut stdout = {
-        let stdout = stdout().into_alternate_screen()?.into_raw_mode()?;
-        HideCursor::from(stdout)
-    };
+//how long the selected piece stays inverted after a rejected move, with `--flash`
+const FLASH_DURATION: Duration = Duration::from_millis(120);
+//how often the terminal size is checked while waiting for a key, so that resizing it redraws the game
+const RESIZE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+//how long solving a board up front can take before saying so, so that quick ones don't flash a message
+const SOLVING_NOTICE_DELAY: Duration = Duration::from_millis(500);
+//how long `--time-limit par` gives for each move of the optimal solution
+const PAR_MOVE_TIME: Duration = Duration::from_secs(10);
+//what `--quiet` exits with when the board can't be solved, or can't be built or parsed
+const EXIT_UNSOLVABLE: i32 = 3;
+const EXIT_INVALID_BOARD: i32 = 4;
+
+//whether the panic hook has to leave the alternate screen
+static IN_ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// runs `f` with the terminal in raw mode and the cursor hidden, restoring it afterwards.
+/// without the alternate screen, the last drawn screen stays in the terminal after exiting
+fn in_terminal<T>(
+    alternate_screen: bool,
+    f: impl FnOnce(&mut dyn Write) -> Result<T>,
+) -> Result<T> {
+    if alternate_screen {
+        IN_ALTERNATE_SCREEN.store(true, Ordering::Relaxed);
+        let mut stdout = HideCursor::from(stdout().into_alternate_screen()?.into_raw_mode()?);
+        let result = f(&mut stdout);
+        //the alternate screen is left when `stdout` is dropped
+        drop(stdout);
+        IN_ALTERNATE_SCREEN.store(false, Ordering::Relaxed);
+        result
+    } else {
+        let mut stdout = HideCursor::from(stdout().into_raw_mode()?);
+        f(&mut stdout)
+    }
+}
+
+fn game_loop(game: Game, alternate_screen: bool) -> Result<Summary> {
+    in_terminal(alternate_screen, |stdout| play(game, stdout))
+}
+
+fn pick_from_menu(alternate_screen: bool) -> Result<Option<menu::Choice>> {
+    in_terminal(alternate_screen, |mut stdout| menu::run(&mut stdout))
+}
+
+/// whether nothing about the board, or anything besides playing it, was asked for on the command line
+fn wants_menu(arg_matches: &ArgMatches) -> bool {
+    let defaulted = ["rows", "cols", "min-moves"]
+        .into_iter()
+        .all(|arg| arg_matches.value_source(arg) == Some(ValueSource::DefaultValue));
+    let flags = [
+        "fit",
+        "default",
+        "tutorial",
+        "solve",
+        "fingerprint",
+        "demo",
+        "allow-trivial",
+    ];
+    let values = [
+        "max-moves",
+        "level",
+        "level-pack",
+        "max-nodes",
+        "export-frames",
+        "export-solution",
+        "solution",
+    ];
 
-    let mut game = Game::new(initial_state)?;
+    termion::is_tty(&stdout())
+        && defaulted
+        && !flags.into_iter().any(|arg| arg_matches.get_flag(arg))
+        && !values.into_iter().any(|arg| arg_matches.contains_id(arg))
+}
+
+/// runs `f`, saying "solving..." on stderr if it takes more than a moment, and erasing it afterwards
+fn with_solving_notice<T>(f: impl FnOnce() -> T) -> T {
+    if !termion::is_tty(&std::io::stderr()) {
+        return f();
+    }
+
+    let (done_sender, done) = mpsc::channel::<()>();
+    let notice = thread::spawn(move || {
+        let shown = done.recv_timeout(SOLVING_NOTICE_DELAY) == Err(RecvTimeoutError::Timeout);
+        if shown {
+            eprint!("solving...");
+        }
+        shown
+    });
+
+    let result = f();
+    drop(done_sender);
+    if notice.join().unwrap_or(false) {
+        eprint!("\r{}", termion::clear::CurrentLine);
+    }
+    result
+}
+
+fn play(mut game: Game, mut stdout: impl Write) -> Result<Summary> {
+    game.draw_intro(&mut stdout)?;
+
+    //keys are read on their own thread, so that autoplaying can go on while waiting for one
+    let (key_sender, keys) = mpsc::channel();
+    thread::spawn(move || {
+        for key in stdin().keys() {
+            if key_sender.send(key).is_err() {
+                break;
+            }
+        }
+    });
+    if let Ok(key) = keys.recv() {
+        key?;
+    }
+
+    //the time spent reading the intro doesn't count
+    game.start_clock();
     game.draw(&mut stdout)?;
+    let mut last_step = Instant::now();
+    let mut last_size = terminal_size().ok();
+    let mut last_countdown = game.countdown();
+
+    loop {
+        let key = if game.is_autoplaying() {
+            //only sleeps for what's left of the interval, since drawing takes time too
+            let wait = game.autoplay_interval().saturating_sub(last_step.elapsed());
+            match keys.recv_timeout(wait) {
+                Ok(key) => key?,
+                Err(RecvTimeoutError::Timeout) => {
+                    last_step = Instant::now();
+                    game.autoplay_step();
+                    game.draw(&mut stdout)?;
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        } else {
+            let auto_new_wait = game.auto_new_wait();
+            let wait =
+                auto_new_wait.map_or(RESIZE_POLL_INTERVAL, |wait| wait.min(RESIZE_POLL_INTERVAL));
+            match keys.recv_timeout(wait) {
+                Ok(key) => key?,
+                Err(RecvTimeoutError::Timeout) if auto_new_wait == Some(wait) => {
+                    if let Err(err) = game.new_board() {
+                        game.cancel_auto_new();
+                        game.set_status(format!("no new board: {err}"));
+                    }
+                    game.draw(&mut stdout)?;
+                    continue;
+                }
+                //redrawing after a resize shows or hides the prompt to resize,
+                //and the countdown is redrawn whenever a second goes by
+                Err(RecvTimeoutError::Timeout) => {
+                    let size = terminal_size().ok();
+                    let timed_out = game.check_time_limit();
+                    let countdown = game.countdown();
+                    if size != last_size || timed_out || countdown != last_countdown {
+                        last_size = size;
+                        last_countdown = countdown;
+                        game.draw(&mut stdout)?;
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        };
 
-    for key in stdin.keys() {
-        let key = key?;
+        //a move made after the time ran out doesn't count
+        if game.check_time_limit() {
+            game.draw(&mut stdout)?;
+        }
+
+        //any key keeps the solved board up, as well as doing what it does
+        if game.auto_new_wait().is_some() {
+            game.cancel_auto_new();
+            game.draw(&mut stdout)?;
+        }
+
+        //nothing can be seen to be played while the terminal is too small
+        if !game.fits_terminal() && !matches!(key, Key::Esc | Key::Ctrl('c')) {
+            continue;
+        }
+
+        //any other key only closes the help, so that moves aren't made without seeing the board
+        if game.is_showing_help() && key != Key::Ctrl('c') {
+            game.dismiss_help();
+            game.draw(&mut stdout)?;
+            continue;
+        }
+
+        //the inspector stays on the rows and columns, since it's only for looking around
+        let diagonal = game.can_move_diagonally() && !game.is_inspecting();
         let action = match (key, game.mode()) {
+            (Key::Up, Mode::Playable) if game.is_inspecting() => {
+                Action::MoveInspector(Direction::Up)
+            }
+            (Key::Down, Mode::Playable) if game.is_inspecting() => {
+                Action::MoveInspector(Direction::Down)
+            }
+            (Key::Left, Mode::Playable) if game.is_inspecting() => {
+                Action::MoveInspector(Direction::Left)
+            }
+            (Key::Right, Mode::Playable) if game.is_inspecting() => {
+                Action::MoveInspector(Direction::Right)
+            }
             (Key::Up, Mode::Playable) => game.move_toward(Direction::Up),
             (Key::Down, Mode::Playable) => game.move_toward(Direction::Down),
             (Key::Left, Mode::Playable) => game.move_toward(Direction::Left),
             (Key::Right, Mode::Playable) => game.move_toward(Direction::Right),
+            (Key::Char('7'), Mode::Playable) if diagonal => game.move_toward(Direction::UpLeft),
+            (Key::Char('9'), Mode::Playable) if diagonal => game.move_toward(Direction::UpRight),
+            (Key::Char('1'), Mode::Playable) if diagonal => game.move_toward(Direction::DownLeft),
+            (Key::Char('3'), Mode::Playable) if diagonal => game.move_toward(Direction::DownRight),
 
             (Key::Char('z'), Mode::Playable) => Action::PrevCharacter,
             (Key::Char('z'), Mode::Walkthrough) => Action::PrevWalkthroughStep,
@@ -37,37 +257,139 @@ fn game_loop(initial_state: State) -> Result<()> {
             (Key::Char('x'), Mode::Playable) => Action::NextCharacter,
             (Key::Char('x'), Mode::Walkthrough) => Action::NextWalkthroughStep,
 
+            (Key::Char('z'), Mode::Review) => Action::ReviewStep(-1),
+            (Key::Char('x'), Mode::Review) => Action::ReviewStep(1),
+            (Key::Char('v'), Mode::GameOver | Mode::Review) => Action::ToggleReview,
+
+            (Key::Char('\n'), Mode::Walkthrough) => Action::PlayFromWalkthroughStep,
+            (Key::Char(' '), Mode::Walkthrough) => Action::ToggleAutoplay,
+            (Key::Char('+'), Mode::Walkthrough) => Action::ChangeSpeed(1),
+            (Key::Char('-'), Mode::Walkthrough) => Action::ChangeSpeed(-1),
+            (Key::Char('o'), Mode::Walkthrough) => Action::CycleObjective,
+
             (Key::Char('u'), Mode::Playable) => Action::Undo,
+            (Key::Char('U'), Mode::Playable) => Action::UndoAll,
+            (Key::Char('y'), Mode::Playable) => Action::Redo,
+            (Key::Char('Y'), Mode::Playable) => Action::RedoAll,
+            (Key::Char('b'), Mode::Playable) => Action::Bookmark,
+            (Key::Char('\''), Mode::Playable) => Action::ReturnToBookmark,
             (Key::Char('r'), _) => Action::Restart,
 
             (Key::Char('w'), _) => Action::ToggleMode,
 
+            (Key::Char('s'), Mode::Playable | Mode::GameOver | Mode::TimeUp) => Action::Screenshot,
+            (Key::Char('p'), Mode::Playable) => Action::TogglePreview,
+            (Key::Char('t'), Mode::Playable) => Action::ToggleTrace,
+            (Key::Char('l'), Mode::Playable) => Action::ToggleVacated,
+            (Key::Char('p'), Mode::Walkthrough) => Action::ToggleStepNumbers,
+            (Key::Char('m'), Mode::Playable) => Action::ToggleMoveSpace,
+            (Key::Char('a'), Mode::Playable) => Action::ToggleAstroReach,
+            (Key::Char('h'), Mode::Playable) => Action::ToggleAssist,
+            (Key::Char('g'), Mode::Playable) => Action::ToggleSolution,
+            (Key::Char('f'), Mode::Playable) => Action::AutoSolve,
+            (Key::Char('d'), Mode::Playable) => Action::ExplainUnsolvable,
+            (Key::Char('e'), Mode::Playable) => Action::TogglePractice,
+            (Key::Char('i'), Mode::Playable) => Action::ToggleInspector,
+            (Key::Char('c'), Mode::Playable | Mode::GameOver | Mode::TimeUp) => {
+                Action::ToggleCoordinates
+            }
+            (Key::Char('n'), _) if game.can_generate_new_board() => Action::NewBoard,
+            (Key::Char('+'), _) if game.can_resize_board() => Action::ResizeBoard(1),
+            (Key::Char('-'), _) if game.can_resize_board() => Action::ResizeBoard(-1),
+            (Key::Char('['), _) if game.can_browse_boards() => Action::BrowseBoard(-1),
+            (Key::Char(']'), _) if game.can_browse_boards() => Action::BrowseBoard(1),
+
+            (Key::Char('?'), _) => Action::ShowHelp,
+
             (Key::Esc | Key::Ctrl('c'), _) => Action::Exit,
+            (Key::Char('q'), Mode::GameOver | Mode::TimeUp) => Action::Exit,
+
+            (_, Mode::GameOver) if game.has_next_board() => Action::NextBoard,
 
             _ => continue,
         };
 
+        game.clear_status();
+        game.clear_rejected_move();
+        if !matches!(action, Action::BrowseBoard(_)) {
+            game.cancel_browse();
+        }
+        //trying to move goes back to playing without the revealed solution
+        if matches!(action, Action::Movement(_)) {
+            game.hide_solution();
+        }
         match action {
             Action::Movement(MovementAttempt::Success(new_pos)) => game.move_selection_to(new_pos),
-            Action::Movement(MovementAttempt::Failure) => continue,
+            Action::Movement(MovementAttempt::Failure) => game.reject_move(),
 
             Action::PrevCharacter => game.select_prev_character(),
             Action::NextCharacter => game.select_next_character(),
 
             Action::Restart => game.restart(),
             Action::Undo => game.undo(),
+            Action::UndoAll => game.undo_all(),
+            Action::Redo => game.redo(),
+            Action::RedoAll => game.redo_all(),
+            Action::Bookmark => game.set_bookmark(),
+            Action::ReturnToBookmark => game.return_to_bookmark(),
 
             Action::Exit => break,
 
             Action::PrevWalkthroughStep => game.walkthrough_prev(),
             Action::NextWalkthroughStep => game.walkthrough_next(),
+            Action::PlayFromWalkthroughStep => game.play_from_walkthrough(),
             Action::ToggleMode => game.toggle_mode(),
+            Action::ToggleReview => game.toggle_review(),
+            Action::ReviewStep(delta) => game.review_step(delta),
+
+            Action::NextBoard => game.next_board()?,
+            Action::BrowseBoard(delta) => game.browse_board(delta)?,
+            Action::ResizeBoard(delta) => game.resize_board(delta),
+            Action::NewBoard => {
+                if let Err(err) = game.new_board() {
+                    game.set_status(format!("no new board: {err}"));
+                }
+            }
+
+            Action::TogglePreview => game.toggle_preview(),
+            Action::ToggleTrace => game.toggle_trace(),
+            Action::ToggleVacated => game.toggle_vacated(),
+            Action::ToggleMoveSpace => game.toggle_move_space(),
+            Action::ToggleAstroReach => game.toggle_astro_reach(),
+            Action::ToggleAssist => game.toggle_assist(),
+            Action::ToggleSolution => game.toggle_solution(),
+            Action::ToggleStepNumbers => game.toggle_step_numbers(),
+            Action::ToggleCoordinates => game.toggle_coordinates(),
+            Action::ToggleInspector => game.toggle_inspector(),
+            Action::MoveInspector(direction) => game.move_inspector(direction),
+            Action::ToggleAutoplay => {
+                last_step = Instant::now();
+                game.toggle_autoplay();
+            }
+            Action::ChangeSpeed(delta) => game.change_speed(delta),
+            Action::AutoSolve => game.auto_solve(),
+            Action::ExplainUnsolvable => game.explain_unsolvable(),
+            Action::TogglePractice => game.toggle_practice(),
+            Action::CycleObjective => game.cycle_objective(),
+            Action::ShowHelp => game.show_help(),
+            Action::Screenshot => {
+                let path = game.screenshot()?;
+                game.set_status(format!("saved to {}", path.display()));
+            }
         };
 
         game.draw(&mut stdout)?;
+        if game.is_flashing() {
+            thread::sleep(FLASH_DURATION);
+            game.clear_rejected_move();
+            game.draw(&mut stdout)?;
+        }
     }
 
-    Ok(())
+    //leave the shell prompt below the last drawn screen
+    writeln!(stdout, "\r")?;
+
+    Ok(game.summary())
 }
 
 fn default_grid() -> Grid<Tile> {
@@ -88,29 +410,560 @@ fn default_grid() -> Grid<Tile> {
     Grid::new(WIDTH, HEIGHT, values.collect())
 }
 
-fn dimension_in_range(dimension: &str) -> Result<usize, String> {
-    let dimension = dimension
-        .parse()
-        .map_err(|_| format!("`{dimension}` is not a valid number"))?;
+fn print_solution(game: &Game, format: &str) -> Result<()> {
+    match format {
+        "keys" => {
+            let keys = game.solution_keystrokes()?;
+            println!("{}", keys.into_iter().map(key_label).join(" "));
+        }
+        "notation" => println!("{}", State::to_notation(game.solution())?),
+        _ => {
+            let solution = game.solution();
+            print_path(solution)?;
+            if let Some(move_costs) = game.move_costs() {
+                println!("cost {}", move_costs.path_cost(solution)?);
+            }
+
+            let robot_moves = MoveCosts { robot: 1, astro: 0 }.path_cost(solution)?;
+            let (_, min_robot_moves) = solution[0]
+                .solve_min_robot_moves()
+                .ok_or_else(|| eyre!("solution found, but no solution minimizing robot moves"))?;
+            println!("{robot_moves} robot moves (fewest possible: {min_robot_moves})");
+        }
+    }
+
+    Ok(())
+}
+
+/// prints the initial state of `solution`, followed by each move
+fn print_path(solution: &[State]) -> Result<()> {
+    println!("{}", solution[0].to_str_grid());
+    println!();
+
+    for change in State::pos_changes(solution) {
+        println!("{}", change?);
+    }
+    println!("{} moves", solution.len() - 1);
+
+    Ok(())
+}
+
+fn print_bounded_solution(initial_state: &State, max_nodes: usize) -> Result<()> {
+    match initial_state.solve_bounded(max_nodes) {
+        SolveOutcome::Solved(solution) => print_path(&solution),
+        SolveOutcome::Unsolved => Err(eyre!("the board cannot be solved")),
+        SolveOutcome::Aborted(explored) => Err(eyre!(
+            "gave up after exploring {explored} positions without finding a solution"
+        )),
+    }
+}
+
+/// prints the optimal solution as a JSON object, which stays valid even if there's an error
+fn print_json_solution(arg_matches: &ArgMatches, dims: (usize, usize)) -> Result<()> {
+    let output = json_solution(arg_matches, dims);
+    match &output {
+        Ok(value) => println!("{value}"),
+        Err(err) => println!("{}", json!({ "error": format!("{err:#}") })),
+    }
+
+    output.map(|_| ())
+}
+
+fn json_solution(arg_matches: &ArgMatches, dims: (usize, usize)) -> Result<serde_json::Value> {
+    let initial_state = match arg_matches.get_one::<String>("level-pack") {
+        Some(name) => {
+            let index = arg_matches
+                .get_one::<usize>("level-index")
+                .copied()
+                .unwrap_or(1);
+            levels::find(name)?
+                .levels()?
+                .into_iter()
+                .nth(index.wrapping_sub(1))
+                .ok_or_else(|| eyre!("{name} has no level {index}"))?
+        }
+        None => initial_state(arg_matches, dims, None)?.0,
+    };
+
+    let weighted = arg_matches.get_flag("weighted");
+    let move_costs = MoveCosts::default();
+    let robot_moves = arg_matches.get_one::<usize>("robot-moves");
+    let solution = if weighted {
+        initial_state.solve_weighted(move_costs.robot, move_costs.astro)
+    } else if let Some(&moves) = robot_moves {
+        let limits = solver::robot_move_limits(initial_state.num_robots(), moves);
+        initial_state
+            .solve_with_move_limits(&limits)
+            .map(|solution| (solution, 0))
+    } else {
+        initial_state
+            .solve_from_here()
+            .map(|solution| (solution, 0))
+    };
+    let Some((solution, cost)) = solution else {
+        return Ok(json!({ "solvable": false, "moves": null, "path": null }));
+    };
+
+    let path: Vec<_> = iter::zip(&solution, State::pos_changes(&solution))
+        .map(|(state, change)| {
+            let PosChange(from, to) = change?;
+            let piece = match state.selection_at(from) {
+                Some(Selection::Astro) => "astro",
+                Some(Selection::Partner) => "partner",
+                Some(Selection::Robot(_)) => "robot",
+                None => return Err(eyre!("no piece to move at {from}")),
+            };
+            let direction = change_direction(&PosChange(from, to))?;
+
+            Ok(json!({
+                "piece": piece,
+                "from": [from.x, from.y],
+                "to": [to.x, to.y],
+                "direction": direction,
+            }))
+        })
+        .try_collect()?;
+
+    let mut output = json!({
+        "solvable": true,
+        "moves": path.len(),
+        "path": path,
+    });
+    if weighted {
+        output["cost"] = json!(cost);
+    }
+    Ok(output)
+}
+
+fn change_direction(change: &PosChange) -> Result<&'static str> {
+    let direction = change
+        .direction()
+        .ok_or_else(|| eyre!("{change} is not a straight move"))?;
+
+    let name = match direction {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+        Direction::UpLeft => "up-left",
+        Direction::UpRight => "up-right",
+        Direction::DownLeft => "down-left",
+        Direction::DownRight => "down-right",
+    };
+    Ok(name)
+}
+
+fn list_levels() -> Result<()> {
+    for pack in levels::packs() {
+        let levels = pack.levels()?;
+        println!("{}: {} levels", pack.name, levels.len());
+    }
+
+    Ok(())
+}
+
+/// prints what's known about the level at `path`, and fails if it can't be played
+fn check_level(arg_matches: &ArgMatches, path: &Path) -> Result<()> {
+    //counting every optimal solution can take long on open boards
+    const MAX_SOLUTIONS: usize = 1000;
+
+    let (state, meta) = read_level(path)
+        .and_then(|(state, meta)| {
+            let state = state
+                .with_stop_rule(stop_rule(arg_matches))
+                .with_collision_rule(collision_rule(arg_matches))
+                .with_movement(movement(arg_matches))
+                .with_win_condition(win_condition(arg_matches))?;
+            Ok((state, meta))
+        })
+        .wrap_err_with(|| format!("{} is not a valid level", path.display()))?;
+
+    let (rows, cols) = state.dims();
+    println!(
+        "{}: {rows}x{cols}, {} robots",
+        path.display(),
+        state.num_robots()
+    );
+    if let Some(name) = &meta.name {
+        println!("name: {name}");
+    }
+    if let Some(author) = &meta.author {
+        println!("author: {author}");
+    }
+    if let Some(stars) = meta.difficulty {
+        println!("difficulty: {stars} of 5");
+    }
+    if let Err(err) = meta.verify_par(&state) {
+        println!("warning: {err:#}");
+    }
+
+    let optimal = state.all_optimal_solutions(MAX_SOLUTIONS);
+    let Some(solution) = optimal.solutions.first() else {
+        let err = GameError::Unsolvable {
+            state: state.to_debug_line(),
+        };
+        return Err(err).wrap_err_with(|| format!("{} cannot be solved", path.display()));
+    };
+
+    let more = if optimal.truncated { "+" } else { "" };
+    println!("optimal moves: {}", solution.len() - 1);
+    println!("optimal solutions: {}{more}", optimal.solutions.len());
+    Ok(())
+}
+
+/// reads a level in the text format, or in the JSON one when `path` ends in `.json`
+fn read_level(path: &Path) -> Result<(State, LevelMeta)> {
+    let level =
+        fs::read_to_string(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        return State::from_level_json(&level);
+    }
+
+    let Level { state, name } = State::parse_level(&level)?;
+    let meta = LevelMeta {
+        name,
+        ..LevelMeta::default()
+    };
+    Ok((state, meta))
+}
+
+fn export_frames(game: &Game, dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).wrap_err_with(|| format!("failed to create {}", dir.display()))?;
+
+    let frames = game.walkthrough_frames()?;
+    //zero-padded so that the files sort in order
+    let width = cmp::max(3, (frames.len() - 1).to_string().len());
+
+    for (i, frame) in frames.iter().enumerate() {
+        let path = dir.join(format!("frame_{i:0width$}.txt"));
+        fs::write(&path, frame).wrap_err_with(|| format!("failed to write {}", path.display()))?;
+    }
+
+    println!("wrote {} frames to {}", frames.len(), dir.display());
+    Ok(())
+}
+
+/// writes the game's first board to `path` in the format `--level` reads.
+/// fails rather than overwrite a file that's already there
+fn write_board(game: &Game, path: &Path) -> Result<()> {
+    ensure!(
+        !path.exists(),
+        "{} already exists, pick another file for --output",
+        path.display()
+    );
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .wrap_err_with(|| format!("failed to create {}", path.display()))?;
+    writeln!(file, "{}", game.solution()[0].to_str_grid())
+        .wrap_err_with(|| format!("failed to write {}", path.display()))
+}
 
-    let acceptable = 4..=10;
+fn key_label(key: Key) -> String {
+    match key {
+        Key::Up => "↑".to_string(),
+        Key::Down => "↓".to_string(),
+        Key::Left => "←".to_string(),
+        Key::Right => "→".to_string(),
+        Key::Char(c) => c.to_string(),
+        key => format!("{key:?}"),
+    }
+}
+
+const MIN_DIMENSION: usize = 4;
+const DEFAULT_MAX_DIMENSION: usize = 10;
+const MAX_DIMENSION_VAR: &str = "ASTRO_MAX_DIM";
+const MAX_DIMENSION_ARG: &str = "max-dim";
+
+/// the board to play, and the options it was generated with if it was randomly generated.
+/// `moves`, when set, overrides how many moves the solution of a generated board takes
+fn initial_state(
+    arg_matches: &ArgMatches,
+    (rows, cols): (usize, usize),
+    moves: Option<RangeInclusive<usize>>,
+) -> Result<(State, Option<GenerationOptions>)> {
+    let win_condition = win_condition(arg_matches);
+
+    if let Some(path) = arg_matches.get_one::<PathBuf>("level") {
+        let (state, meta) = read_level(path)?;
+        let state = state
+            .with_stop_rule(stop_rule(arg_matches))
+            .with_collision_rule(collision_rule(arg_matches))
+            .with_movement(movement(arg_matches))
+            .with_win_condition(win_condition)?;
+        if let Err(err) = meta.verify_par(&state) {
+            log::warn!("{}: {err:#}", path.display());
+        }
+        Ok((state, None))
+    } else if arg_matches.get_flag("default") {
+        let state = State::from_grid(&default_grid())?
+            .with_stop_rule(stop_rule(arg_matches))
+            .with_collision_rule(collision_rule(arg_matches))
+            .with_movement(movement(arg_matches))
+            .with_win_condition(win_condition)?;
+        Ok((state, None))
+    } else {
+        let mut options = generation_options(arg_matches);
+        if let Some(moves) = moves {
+            options.moves = moves;
+        }
+        let generated = State::generate(rows, cols, &options)?;
+        Ok((generated.state, Some(options)))
+    }
+}
+
+fn generation_options(arg_matches: &ArgMatches) -> GenerationOptions {
+    let attempts = *arg_matches
+        .get_one::<usize>("attempts")
+        .expect("default value");
+
+    //every board is solved in at least one move, since the astro never starts on a goal
+    let min_moves = if arg_matches.get_flag("allow-trivial") {
+        1
+    } else {
+        *arg_matches
+            .get_one::<usize>("min-moves")
+            .expect("default value")
+    };
+    let max_moves = arg_matches
+        .get_one::<usize>("max-moves")
+        .copied()
+        .unwrap_or(usize::MAX);
+
+    GenerationOptions {
+        attempts,
+        max_attempts: attempts.saturating_mul(8),
+        win_condition: win_condition(arg_matches),
+        stop_rule: stop_rule(arg_matches),
+        collision_rule: collision_rule(arg_matches),
+        movement: movement(arg_matches),
+        robot_moves: arg_matches.get_one::<usize>("robot-moves").copied(),
+        min_robots: *arg_matches
+            .get_one::<usize>("min-robots")
+            .expect("default value"),
+        moves: min_moves..=max_moves,
+        placement: match arg_matches
+            .get_one::<String>("placement")
+            .expect("default value")
+            .as_str()
+        {
+            "near-route" => Placement::NearRoute,
+            _ => Placement::Uniform,
+        },
+        partner: arg_matches.get_flag("two-astros"),
+        require_robot_moves: arg_matches.get_flag("require-robot-moves"),
+        ..GenerationOptions::default()
+    }
+}
+
+fn parse_labels(value: &str) -> Result<Labels, String> {
+    let names = value.split(',').map(str::trim).collect_vec();
+    match names[..] {
+        [astro, robot] if !astro.is_empty() && !robot.is_empty() => Ok(Labels {
+            astro: astro.to_string(),
+            robot: robot.to_string(),
+            robots: format!("{robot}s"),
+        }),
+        [astro, robot, robots] if ![astro, robot, robots].contains(&"") => Ok(Labels {
+            astro: astro.to_string(),
+            robot: robot.to_string(),
+            robots: robots.to_string(),
+        }),
+        _ => Err(
+            "expected the astro's name and the robots', like cat,mouse or cat,mouse,mice"
+                .to_string(),
+        ),
+    }
+}
+
+fn parse_time_limit(value: &str) -> Result<TimeLimit, String> {
+    if value == "par" {
+        return Ok(TimeLimit::PerParMove(PAR_MOVE_TIME));
+    }
+    match value.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(TimeLimit::Fixed(Duration::from_secs(seconds))),
+        _ => Err("expected a number of seconds from 1, or par".to_string()),
+    }
+}
+
+fn parse_seed_range(value: &str) -> Result<Range<u64>, String> {
+    let bounds = value
+        .split_once("..")
+        .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)));
+    match bounds {
+        Some((start, end)) if start < end => Ok(start..end),
+        _ => Err("expected a range of seeds like 0..1000, the end excluded".to_string()),
+    }
+}
+
+fn parse_goal_occupant(value: &str) -> Result<String, String> {
+    match value {
+        "astro" | "any-robot" => Ok(value.to_string()),
+        _ => match value.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(value.to_string()),
+            _ => Err("expected astro, any-robot, or a robot number from 1".to_string()),
+        },
+    }
+}
+
+fn win_condition(arg_matches: &ArgMatches) -> WinCondition {
+    if arg_matches.get_flag("cooperative") {
+        return WinCondition::AllPieces;
+    }
+
+    match arg_matches
+        .get_one::<String>("goal-occupant")
+        .map(String::as_str)
+    {
+        Some("any-robot") => WinCondition::Occupant(GoalOccupant::AnyRobot),
+        //validated by the value parser, and numbered from 1
+        Some(n) if n != "astro" => {
+            let n: usize = n.parse().expect("robot numbers are validated");
+            WinCondition::Occupant(GoalOccupant::Robot(n - 1))
+        }
+        _ => WinCondition::AstroOnly,
+    }
+}
+
+fn stop_rule(arg_matches: &ArgMatches) -> StopRule {
+    match arg_matches
+        .get_one::<String>("stop-rule")
+        .expect("default value")
+        .as_str()
+    {
+        "on-goal" => StopRule::OnGoal,
+        "robots-avoid-goal" => StopRule::RobotsAvoidGoal,
+        _ => StopRule::Adjacent,
+    }
+}
+
+fn collision_rule(arg_matches: &ArgMatches) -> CollisionRule {
+    if arg_matches.get_flag("push") {
+        CollisionRule::Push
+    } else {
+        CollisionRule::Stop
+    }
+}
+
+fn movement(arg_matches: &ArgMatches) -> Movement {
+    if arg_matches.get_flag("eight-way") {
+        Movement::EightWay
+    } else {
+        Movement::Orthogonal
+    }
+}
+
+/// the largest accepted dimension, taken from `--max-dim`, then the environment, then the default.
+fn max_dimension(arg_matches: &ArgMatches) -> Result<usize, String> {
+    let max_dimension = match arg_matches.get_one(MAX_DIMENSION_ARG) {
+        Some(&max_dimension) => max_dimension,
+        None => match env::var(MAX_DIMENSION_VAR) {
+            Ok(var) => var
+                .parse()
+                .map_err(|_| format!("{MAX_DIMENSION_VAR}=`{var}` is not a valid number"))?,
+            Err(_) => DEFAULT_MAX_DIMENSION,
+        },
+    };
+
+    //below this, the generator can't fit astro, the goal and robots on the grid
+    (max_dimension >= MIN_DIMENSION)
+        .then_some(max_dimension)
+        .ok_or_else(|| format!("maximum dimension must be at least {MIN_DIMENSION}"))
+}
+
+fn dimension_in_range(dimension: usize, max_dimension: usize) -> Result<usize, String> {
+    let acceptable = MIN_DIMENSION..=max_dimension;
     acceptable
         .contains(&dimension)
         .then_some(dimension)
         .ok_or_else(|| format!("{dimension} is out of range. acceptable range: {acceptable:?}"))
 }
 
+/// reads a dimension argument, exiting with a usage error if it's outside the acceptable range.
+fn dimension_arg(command: &mut Command, arg_matches: &ArgMatches, arg: &str) -> usize {
+    let dimension = arg_matches.get_one(arg).copied().expect("default value");
+
+    max_dimension(arg_matches)
+        .and_then(|max_dimension| dimension_in_range(dimension, max_dimension))
+        .unwrap_or_else(|err| {
+            let message = format!("invalid value for --{arg}: {err}");
+            command.error(ErrorKind::ValueValidation, message).exit()
+        })
+}
+
+/// the largest (rows, cols) in the acceptable range that fit the current terminal
+fn fitting_dimensions(arg_matches: &ArgMatches) -> Result<(usize, usize)> {
+    let terminal_size = terminal_size().wrap_err("--fit needs to know the terminal size")?;
+    let grid_lines = arg_matches.contains_id("grid-lines");
+    let gaps = (arg_matches.get_flag("spacing") || grid_lines, grid_lines);
+    let (rows, cols) = game::max_fitting_dims(terminal_size, gaps);
+
+    ensure!(
+        rows >= MIN_DIMENSION && cols >= MIN_DIMENSION,
+        "the terminal is too small: only {rows}x{cols} tiles fit, \
+        but at least {MIN_DIMENSION}x{MIN_DIMENSION} are needed"
+    );
+
+    let max_dimension = max_dimension(arg_matches).map_err(|err| eyre!(err))?;
+    Ok((rows.min(max_dimension), cols.min(max_dimension)))
+}
+
+/// like `color_eyre::install`, but panics restore the terminal before they are reported.
+/// otherwise the report would be printed into the alternate screen in raw mode,
+/// which are only torn down afterwards, while unwinding.
+fn install_hooks() -> Result<()> {
+    let (panic_hook, eyre_hook) = HookBuilder::default().into_hooks();
+    eyre_hook.install()?;
+
+    //taken before entering raw mode, the same way termion does it
+    let original_attr = unsafe {
+        let mut termios = mem::zeroed();
+        (libc::tcgetattr(libc::STDOUT_FILENO, &mut termios) == 0).then_some(termios)
+    };
+
+    panic::set_hook(Box::new(move |panic_info| {
+        if let Some(termios) = original_attr {
+            unsafe { libc::tcsetattr(libc::STDOUT_FILENO, libc::TCSANOW, &termios) };
+        }
+        if IN_ALTERNATE_SCREEN.load(Ordering::Relaxed) {
+            print!("{}", screen::ToMainScreen);
+        }
+        print!("{}", cursor::Show);
+        let _ = stdout().flush();
+
+        eprintln!("{}", panic_hook.panic_report(panic_info));
+    }));
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
-    color_eyre::install()?;
+    install_hooks()?;
+
+    let mut command = command();
+    let arg_matches = command.get_matches_mut();
 
-    let arg_matches = Command::new("Astro and Robots")
+    let quiet = arg_matches.get_flag("quiet");
+    match run(command, arg_matches) {
+        Err(report) if quiet => exit_quietly(report),
+        result => result,
+    }
+}
+
+/// every argument the game takes
+fn command() -> Command {
+    Command::new("Astro and Robots")
         .arg(
             Arg::new("rows")
                 .short('r')
                 .long("rows")
                 .help("Number of rows in grid")
                 .default_value("5")
-                .value_parser(dimension_in_range),
+                .value_parser(clap::value_parser!(usize)),
         )
         .arg(
             Arg::new("cols")
@@ -118,7 +971,23 @@ fn main() -> Result<()> {
                 .long("cols")
                 .help("Number of columns in grid")
                 .default_value("5")
-                .value_parser(dimension_in_range),
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("fit")
+                .long("fit")
+                .help("Use the largest grid that fits the terminal")
+                .conflicts_with_all(["rows", "cols"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(MAX_DIMENSION_ARG)
+                .long(MAX_DIMENSION_ARG)
+                .help(format!(
+                    "Largest accepted number of rows or columns [default: ${MAX_DIMENSION_VAR} or {DEFAULT_MAX_DIMENSION}]"
+                ))
+                .global(true)
+                .value_parser(clap::value_parser!(usize)),
         )
         .arg(
             Arg::new("default")
@@ -126,18 +995,847 @@ fn main() -> Result<()> {
                 .help("Use the predefined default instead of randomly-generating the grid")
                 .action(ArgAction::SetTrue),
         )
-        .get_matches();
+        .arg(
+            Arg::new("attempts")
+                .long("attempts")
+                .help("Number of random boards to try before raising the budget, up to 8 times as many")
+                .global(true)
+                .default_value("5000")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("min-robots")
+                .long("min-robots")
+                .help("Fewest robots a randomly-generated grid can have")
+                .global(true)
+                .default_value("0")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("placement")
+                .long("placement")
+                .help("Where randomly-generated grids put their robots: anywhere, or mostly around the way from the astro to the goal")
+                .global(true)
+                .default_value("uniform")
+                .value_parser(["uniform", "near-route"]),
+        )
+        .arg(
+            Arg::new("two-astros")
+                .long("two-astros")
+                .help("Add a second astro to randomly-generated grids, which needs a goal of its own")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("require-robot-moves")
+                .long("require-robot-moves")
+                .help("Only randomly-generate grids that can't be solved without moving a robot")
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("min-moves")
+                .long("min-moves")
+                .help("Fewest moves the solution of a randomly-generated grid can take")
+                .global(true)
+                .default_value("4")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("allow-trivial")
+                .long("allow-trivial")
+                .help("Accept any solvable randomly-generated grid, however few moves it takes, which is the quickest to generate")
+                .global(true)
+                .conflicts_with("min-moves")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("max-moves")
+                .long("max-moves")
+                .help("Most moves the solution of a randomly-generated grid can take")
+                .global(true)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("level")
+                .long("level")
+                .help("Load the grid from a text file, or a JSON one ending in .json, instead of randomly-generating it")
+                .conflicts_with("default")
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("level-pack")
+                .long("level-pack")
+                .help("Play through one of the bundled level packs, moving on to the next level whenever one is solved")
+                .value_name("NAME")
+                .conflicts_with_all(["default", "level"])
+                .value_parser(PossibleValuesParser::new(levels::packs().iter().map(|pack| pack.name))),
+        )
+        .arg(
+            Arg::new("level-index")
+                .long("level-index")
+                .help("Level of the pack to start from [default: 1]")
+                .value_name("N")
+                .requires("level-pack")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("check")
+                .long("check")
+                .help("Check that a level file parses and can be solved, print its optimal solutions and exit")
+                .value_name("FILE")
+                .conflicts_with_all(["default", "level", "level-pack"])
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("list-levels")
+                .long("list-levels")
+                .help("Print the bundled level packs and exit")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("tutorial")
+                .long("tutorial")
+                .help("Play through a short sequence of boards introducing the rules")
+                .conflicts_with_all(["default", "level", "level-pack"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("cooperative")
+                .long("cooperative")
+                .help("Every piece, not just the astro, has to end up on a goal")
+                .conflicts_with_all(["tutorial", "level-pack"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("goal-occupant")
+                .long("goal-occupant")
+                .help("Which piece has to reach the goal: astro, any-robot, or the number of a robot, counting from 1 column by column")
+                .value_name("PIECE")
+                .conflicts_with_all(["tutorial", "level-pack", "cooperative"])
+                .value_parser(parse_goal_occupant),
+        )
+        .arg(
+            Arg::new("stop-rule")
+                .long("stop-rule")
+                .help("Where sliding pieces stop: next to another piece, also on goals, or, for robots, never on goals")
+                .conflicts_with_all(["tutorial", "level-pack"])
+                .default_value("adjacent")
+                .value_parser(["adjacent", "on-goal", "robots-avoid-goal"]),
+        )
+        .arg(
+            Arg::new("push")
+                .long("push")
+                .help("Pieces push a robot they run into, which slides on until it stops, and stop right behind it")
+                .conflicts_with_all(["tutorial", "level-pack"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("eight-way")
+                .long("eight-way")
+                .help("Let pieces slide diagonally too, with 7, 9, 1 and 3 as on a numeric keypad")
+                .conflicts_with_all(["tutorial", "level-pack", "race"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("robot-moves")
+                .long("robot-moves")
+                .help("Let each robot be moved at most this many times, counting the times it gets pushed")
+                .value_name("N")
+                .global(true)
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("weighted")
+                .long("weighted")
+                .help("Score robot moves as 2 and astro moves as 1, and find the cheapest solution")
+                //declared here rather than on the global --robot-moves, since subcommands don't have this
+                .conflicts_with("robot-moves")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("remaining")
+                .long("remaining")
+                .help("Show how many moves the optimal solution from each position takes (slow on large grids)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("assist")
+                .long("assist")
+                .help("Shade where the next optimal move starts and ends, as a learning aid (h toggles it)")
+                .conflicts_with("hardcore")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("guided")
+                .long("guided")
+                .help("Show the optimal solution one move at a time, and only accept moves that stay optimal")
+                .conflicts_with_all(["hardcore", "race"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("auto-new")
+                .long("auto-new")
+                .help("Start a new random board a moment after each one is solved, keeping a tally of the session")
+                .conflicts_with_all(["default", "level", "tutorial", "level-pack", "race"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("stats-after")
+                .long("stats-after")
+                .help("Print a one-line summary of the game (result, moves, par, undos, time) after quitting")
+                .conflicts_with("race")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("race")
+                .long("race")
+                .help("Two players race to solve the same board side by side, one with wasd and q/e, the other with the arrows and ,/.")
+                .conflicts_with_all(["tutorial", "level-pack", "solve", "max-nodes", "export-frames", "export-solution"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("demo")
+                .long("demo")
+                .help("Play randomly-generated boards by themselves, one after the other, until a key is pressed")
+                .conflicts_with_all(["default", "level", "level-pack", "tutorial", "race", "solve", "max-nodes", "export-frames", "export-solution"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("undo-limit")
+                .long("undo-limit")
+                .help("Only keep enough history to undo this many moves, for very long sessions")
+                .value_name("N")
+                .conflicts_with_all(["hardcore", "robot-moves", "race"])
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("hardcore")
+                .long("hardcore")
+                .help("Disable undo, redo and restarting, so that every move is final")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("warn-unsolvable")
+                .long("warn-unsolvable")
+                .help("Warn whenever a move makes the board unsolvable (slow on large grids)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("accessible")
+                .long("accessible")
+                .help("Mark selected and moved pieces with underlines and symbols, not just colors")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("bell")
+                .long("bell")
+                .help("Ring the terminal bell when a piece can't move the way it's told to")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("flash")
+                .long("flash")
+                .help("Briefly flash the selected piece when it can't move the way it's told to")
+                .conflicts_with("bell")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("silent")
+                .long("silent")
+                .help("Give no feedback when a piece can't move the way it's told to [default]")
+                .conflicts_with_all(["bell", "flash"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("no-alt-screen")
+                .long("no-alt-screen")
+                .help("Play in the main screen, so that the last board stays visible after exiting")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("spacing")
+                .long("spacing")
+                .help("Draw a space between tiles, so that columns are easier to tell apart")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("grid-lines")
+                .long("grid-lines")
+                .help("Draw lines between tiles and rows, with box-drawing characters or in plain ascii")
+                .value_name("STYLE")
+                .num_args(0..=1)
+                .default_missing_value("box")
+                .value_parser(["box", "ascii"]),
+        )
+        .arg(
+            Arg::new("fog")
+                .long("fog")
+                .help("Only show the tiles within this many steps of the astro, along with the goal and the selected piece")
+                .value_name("RADIUS")
+                .conflicts_with("race")
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("time-limit")
+                .long("time-limit")
+                .help("Solve each board within this many seconds, or with par, within 10 seconds for each move of the optimal solution")
+                .value_name("SECONDS")
+                .conflicts_with("race")
+                .value_parser(parse_time_limit),
+        )
+        .arg(
+            Arg::new("speed")
+                .long("speed")
+                .help(format!(
+                    "Steps per second when autoplaying the walkthrough with space, from 1 to {MAX_SPEED}"
+                ))
+                .value_name("FPS")
+                .default_value("2")
+                .value_parser(clap::value_parser!(u32).range(1..=i64::from(MAX_SPEED))),
+        )
+        .arg(
+            Arg::new("piece-names")
+                .long("piece-names")
+                .help("What to call the astro and the robots, followed by the robots' plural if it isn't just an added s")
+                .value_name("ASTRO,ROBOT[,ROBOTS]")
+                .value_parser(parse_labels),
+        )
+        .arg(
+            Arg::new("goal-style")
+                .long("goal-style")
+                .help("How much the goals stand out, even with pieces on them")
+                .default_value("plain")
+                .value_parser(["plain", "bold", "blink"]),
+        )
+        .arg(
+            Arg::new("no-wrap-selection")
+                .long("no-wrap-selection")
+                .help("Stop selecting the next piece at the last one, and the previous piece at the first one, instead of going around")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("render-priority")
+                .long("render-priority")
+                .help("What's drawn where a piece rests on a goal: the piece, or the goal with the piece marked on it")
+                .default_value("pieces")
+                .value_parser(["pieces", "goal"]),
+        )
+        .arg(
+            Arg::new("solve")
+                .long("solve")
+                .help("Print the optimal solution instead of playing")
+                .conflicts_with("tutorial")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("fingerprint")
+                .long("fingerprint")
+                .help("Print a hash of the board that's the same for its rotations and reflections, and exit. with --level-pack, one for each level")
+                .conflicts_with_all(["tutorial", "solve", "race"])
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("max-nodes")
+                .long("max-nodes")
+                .help("Give up on --solve after exploring this many positions (text format only)")
+                .value_name("N")
+                .requires("solve")
+                .conflicts_with_all(["format", "weighted", "level-pack", "robot-moves"])
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("export-frames")
+                .long("export-frames")
+                .help("Write each step of the optimal solution to a text file in this directory, instead of playing")
+                .value_name("DIR")
+                .conflicts_with_all(["tutorial", "solve"])
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("export-solution")
+                .long("export-solution")
+                .help("Write the optimal solution to this file, one move per line, instead of playing")
+                .value_name("FILE")
+                .conflicts_with_all(["tutorial", "solve", "export-frames"])
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .help("Write the first board to this file before playing, to load again with --level. Never overwrites an existing file")
+                .value_name("FILE")
+                .conflicts_with_all(["tutorial", "race", "fingerprint"])
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("solution")
+                .long("solution")
+                .help("Start in the walkthrough of the solution in this file, as written by --export-solution or --format notation")
+                .value_name("FILE")
+                .conflicts_with_all(["tutorial", "level-pack", "weighted", "robot-moves"])
+                .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("How --solve prints the solution")
+                .requires("solve")
+                .default_value("text")
+                .value_parser(["text", "keys", "json", "notation"]),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .help("Log generation and solving to stderr at this level, instead of using RUST_LOG. Redirect stderr when playing")
+                .global(true)
+                .value_name("LEVEL")
+                .value_parser(["error", "warn", "info", "debug", "trace"]),
+        )
+        .arg(
+            Arg::new("quiet")
+                .long("quiet")
+                .help(format!(
+                    "Report an unsolvable board in one line and exit with {EXIT_UNSOLVABLE}, or with {EXIT_INVALID_BOARD} for one that can't be built or parsed"
+                ))
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .subcommand(
+            Command::new("validate-all-sizes")
+                .about("Check that boards of every allowed size generate and can be solved")
+                .hide(true)
+                .arg(
+                    Arg::new("seeds")
+                        .long("seeds")
+                        .help("Number of seeds to generate each size with")
+                        .default_value("3")
+                        .value_parser(clap::value_parser!(u64)),
+                ),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Measure board generation and solve throughput")
+                .arg(
+                    Arg::new("size")
+                        .short('s')
+                        .long("size")
+                        .help("Number of rows and columns in each generated grid")
+                        .default_value("5")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("count")
+                        .short('n')
+                        .long("count")
+                        .help("Number of boards to generate")
+                        .default_value("100")
+                        .value_parser(clap::value_parser!(usize)),
+                ),
+        )
+        .subcommand(
+            Command::new("scan")
+                .about("Show how long the optimal solutions are over a range of seeds, to pick out boards from")
+                .arg(
+                    Arg::new("size")
+                        .short('s')
+                        .long("size")
+                        .help("Number of rows and columns in each generated grid")
+                        .default_value("5")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    Arg::new("seeds")
+                        .long("seeds")
+                        .help("Seeds to generate a board with, like 0..1000, the end excluded")
+                        .default_value("0..100")
+                        .value_parser(parse_seed_range),
+                ),
+        )
+}
+
+/// with `--quiet`, an error caused by the board rather than by something going wrong is reported
+/// in a single line, without the backtrace, and exits with a code of its own. others are reported as usual
+fn exit_quietly(report: Report) -> Result<()> {
+    let Some(err) = report
+        .chain()
+        .find_map(|err| err.downcast_ref::<GameError>())
+    else {
+        return Err(report);
+    };
+    let code = match err {
+        GameError::Unsolvable { .. } | GameError::GaveUp { .. } => EXIT_UNSOLVABLE,
+        _ => EXIT_INVALID_BOARD,
+    };
+
+    eprintln!("{report:#}");
+    process::exit(code)
+}
+
+fn run(mut command: Command, arg_matches: ArgMatches) -> Result<()> {
+    //an explicit level takes precedence over RUST_LOG
+    let mut logger = env_logger::Builder::from_default_env();
+    if let Some(level) = arg_matches.get_one::<String>("log-level") {
+        logger.parse_filters(level);
+    }
+    logger.init();
+
+    //NO_COLOR asks for no colors when set to anything, see https://no-color.org
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    game::set_colors(!no_color && stdout().is_terminal());
+
+    if let Some(("bench", bench_matches)) = arg_matches.subcommand() {
+        let bench_command = command
+            .find_subcommand_mut("bench")
+            .expect("matched subcommand");
+        let size = dimension_arg(bench_command, bench_matches, "size");
+        let count = bench_matches
+            .get_one("count")
+            .copied()
+            .expect("default value");
+        return bench::run(size, count, generation_options(&arg_matches));
+    }
+
+    if let Some(("scan", scan_matches)) = arg_matches.subcommand() {
+        let scan_command = command
+            .find_subcommand_mut("scan")
+            .expect("matched subcommand");
+        let size = dimension_arg(scan_command, scan_matches, "size");
+        let seeds = scan_matches
+            .get_one::<Range<u64>>("seeds")
+            .cloned()
+            .expect("default value");
+        return scan::run(size, seeds, generation_options(&arg_matches));
+    }
+
+    if let Some(("validate-all-sizes", validate_matches)) = arg_matches.subcommand() {
+        let max_dimension = max_dimension(&arg_matches).map_err(|err| eyre!(err))?;
+        let seeds = validate_matches
+            .get_one("seeds")
+            .copied()
+            .expect("default value");
+        return selftest::run(
+            MIN_DIMENSION..=max_dimension,
+            seeds,
+            generation_options(&arg_matches),
+        );
+    }
+
+    if arg_matches.get_flag("list-levels") {
+        return list_levels();
+    }
+
+    if let Some(path) = arg_matches.get_one::<PathBuf>("check") {
+        return check_level(&arg_matches, path);
+    }
 
-    let [rows, cols] =
-        ["rows", "cols"].map(|arg| arg_matches.get_one(arg).copied().expect("default value"));
+    let alternate_screen = !arg_matches.get_flag("no-alt-screen");
+    let choice = if wants_menu(&arg_matches) {
+        match pick_from_menu(alternate_screen)? {
+            Some(choice) => Some(choice),
+            None => return Ok(()),
+        }
+    } else {
+        None
+    };
 
-    let initial_state = if arg_matches.get_flag("default") {
-        State::from_grid(&default_grid())
+    let (rows, cols) = if let Some(choice) = &choice {
+        (choice.size, choice.size)
+    } else if arg_matches.get_flag("fit") {
+        fitting_dimensions(&arg_matches)?
     } else {
-        State::new_randomized(rows, cols)
+        let [rows, cols] =
+            ["rows", "cols"].map(|arg| dimension_arg(&mut command, &arg_matches, arg));
+        (rows, cols)
+    };
+
+    //unlike the other formats, an unsolvable board is still a result rather than an error
+    if arg_matches.get_flag("solve")
+        && arg_matches
+            .get_one::<String>("format")
+            .expect("default value")
+            == "json"
+    {
+        return print_json_solution(&arg_matches, (rows, cols));
+    }
+
+    let game = if arg_matches.get_flag("tutorial") {
+        Game::new_tutorial(tutorial::boards()?)
+    } else if let Some(name) = arg_matches.get_one::<String>("level-pack") {
+        let pack = levels::find(name)?;
+        if arg_matches.get_flag("fingerprint") {
+            for (i, level) in pack.levels()?.iter().enumerate() {
+                println!("{}: {:016x}", i + 1, level.fingerprint());
+            }
+            return Ok(());
+        }
+
+        let index = arg_matches.get_one("level-index").copied().unwrap_or(1);
+        ensure!(index > 0, "levels are numbered from 1");
+        let levels = pack.levels()?;
+        with_solving_notice(|| Game::new_sequence(pack.name, levels, index - 1))
+    } else {
+        let moves = choice.map(|choice| choice.moves);
+        let (initial_state, generation) = initial_state(&arg_matches, (rows, cols), moves)?;
+
+        //the board doesn't have to be solvable to be told apart from others
+        if arg_matches.get_flag("fingerprint") {
+            println!("{:016x}", initial_state.fingerprint());
+            return Ok(());
+        }
+
+        if arg_matches.get_flag("race") {
+            let games = with_solving_notice(|| -> Result<_> {
+                Ok([
+                    Game::new(initial_state.clone())?.without_game_over_menu(),
+                    Game::new(initial_state)?.without_game_over_menu(),
+                ])
+            })?;
+            return in_terminal(alternate_screen, |mut stdout| race::run(games, &mut stdout));
+        }
+
+        //solving is bounded, so it can't happen when the game solves the board up front
+        if let Some(&max_nodes) = arg_matches.get_one::<usize>("max-nodes") {
+            return print_bounded_solution(&initial_state, max_nodes);
+        }
+
+        let game = with_solving_notice(|| Game::new(initial_state))?;
+        match generation {
+            Some(options) => {
+                let max_dimension = max_dimension(&arg_matches).map_err(|err| eyre!(err))?;
+                Ok(game
+                    .with_generation(options)
+                    .with_board_sizes(MIN_DIMENSION..=max_dimension))
+            }
+            None => Ok(game),
+        }
     }?;
 
-    game_loop(initial_state)?;
+    let game = if arg_matches.get_flag("weighted") {
+        game.with_move_costs(MoveCosts::default())?
+    } else {
+        game
+    };
+    let game = match arg_matches.get_one::<usize>("robot-moves") {
+        Some(&moves) => game.with_robot_move_limit(moves)?,
+        None => game,
+    };
+    let game = match arg_matches.get_one::<usize>("undo-limit") {
+        Some(&limit) => game.with_undo_limit(limit)?,
+        None => game,
+    };
+    let game = if arg_matches.get_flag("remaining") {
+        game.with_remaining_optimal()
+    } else {
+        game
+    };
+
+    let game = if arg_matches.get_flag("hardcore") {
+        game.with_hardcore()
+    } else {
+        game
+    };
+    let game = if arg_matches.get_flag("auto-new") {
+        game.with_auto_new()?
+    } else {
+        game
+    };
+    let game = if arg_matches.get_flag("assist") {
+        game.with_assist()
+    } else {
+        game
+    };
+    let game = if arg_matches.get_flag("guided") {
+        game.with_guided()
+    } else {
+        game
+    };
+    let game = if arg_matches.get_flag("warn-unsolvable") {
+        game.with_unsolvable_warning()
+    } else {
+        game
+    };
+    //without colors, the accessible glyphs are what tell pieces on goals apart
+    let game = if arg_matches.get_flag("accessible") || !game::colors_enabled() {
+        game.with_accessible()
+    } else {
+        game
+    };
+    let move_feedback = if arg_matches.get_flag("bell") {
+        MoveFeedback::Bell
+    } else if arg_matches.get_flag("flash") {
+        MoveFeedback::Flash
+    } else {
+        MoveFeedback::Silent
+    };
+    let game = game.with_move_feedback(move_feedback);
+
+    let goal_style = match arg_matches
+        .get_one::<String>("goal-style")
+        .expect("default value")
+        .as_str()
+    {
+        "bold" => GoalStyle::Bold,
+        "blink" => GoalStyle::Blink,
+        _ => GoalStyle::Plain,
+    };
+    let game = game.with_goal_style(goal_style);
+    let game = if arg_matches.get_flag("no-wrap-selection") {
+        game.without_selection_wrapping()
+    } else {
+        game
+    };
+    let render_priority = match arg_matches
+        .get_one::<String>("render-priority")
+        .expect("default value")
+        .as_str()
+    {
+        "goal" => RenderPriority::Goal,
+        _ => RenderPriority::Pieces,
+    };
+    let game = game.with_render_priority(render_priority);
+    let game = match arg_matches.get_one::<Labels>("piece-names") {
+        Some(labels) => game.with_labels(labels.clone()),
+        None => game,
+    };
+    let game = game.with_speed(*arg_matches.get_one::<u32>("speed").expect("default value"));
+    let game = if arg_matches.get_flag("spacing") {
+        game.with_spacing()
+    } else {
+        game
+    };
+    let game = match arg_matches
+        .get_one::<String>("grid-lines")
+        .map(String::as_str)
+    {
+        Some("ascii") => game.with_grid_lines(GridLines::Ascii),
+        Some(_) => game.with_grid_lines(GridLines::Box),
+        None => game,
+    };
+    let game = match arg_matches.get_one::<usize>("fog") {
+        Some(&radius) => game.with_fog(radius),
+        None => game,
+    };
+    let game = match arg_matches.get_one::<TimeLimit>("time-limit") {
+        Some(&limit) => game.with_time_limit(limit),
+        None => game,
+    };
+
+    let game = match arg_matches.get_one::<PathBuf>("solution") {
+        Some(path) => game.with_imported_solution(path)?,
+        None => game,
+    };
+
+    if let Some(path) = arg_matches.get_one::<PathBuf>("output") {
+        write_board(&game, path)?;
+    }
+
+    if let Some(path) = arg_matches.get_one::<PathBuf>("export-solution") {
+        game.export_solution(path)?;
+        println!(
+            "wrote {} moves to {}",
+            game.solution().len() - 1,
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if let Some(dir) = arg_matches.get_one::<PathBuf>("export-frames") {
+        return export_frames(&game, dir);
+    }
+
+    if arg_matches.get_flag("solve") {
+        let format = arg_matches
+            .get_one::<String>("format")
+            .expect("default value");
+        return print_solution(&game, format);
+    }
+
+    if arg_matches.get_flag("demo") {
+        return in_terminal(alternate_screen, |mut stdout| demo::run(game, &mut stdout));
+    }
+
+    let summary = game_loop(game, alternate_screen)?;
+    if arg_matches.get_flag("stats-after") {
+        print_summary(&summary);
+    }
 
     Ok(())
 }
+
+/// prints `summary` as a single line of `key=value` pairs, so that it's easy to grep and parse
+fn print_summary(summary: &Summary) {
+    let result = if summary.solved {
+        "solved"
+    } else {
+        "abandoned"
+    };
+    //how close an unsolved attempt came, since it's always 0 for a solved one
+    let closest = if summary.solved {
+        String::new()
+    } else {
+        format!(" closest={}", summary.closest)
+    };
+    println!(
+        "result={result} moves={} par={} undos={} time={:.1}s{closest}",
+        summary.moves,
+        summary.par,
+        summary.undos,
+        summary.time.as_secs_f64()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generation_options_for(args: &[&str]) -> GenerationOptions {
+        let arg_matches = command()
+            .try_get_matches_from(iter::once("astro_and_robots").chain(args.iter().copied()))
+            .expect("valid arguments");
+        generation_options(&arg_matches)
+    }
+
+    /// the fewest moves any of the boards generated with `options` takes, over a few seeds
+    fn fewest_generated_moves(options: GenerationOptions) -> usize {
+        (0..20)
+            .map(|seed| {
+                let options = GenerationOptions {
+                    seed: Some(seed),
+                    ..options.clone()
+                };
+                let state = State::generate(4, 4, &options).expect("a board").state;
+                state.solve_from_here().expect("a solution").len() - 1
+            })
+            .min()
+            .expect("some seeds")
+    }
+
+    #[test]
+    fn allow_trivial_accepts_boards_of_a_single_move() {
+        let options = generation_options_for(&["--allow-trivial"]);
+        assert_eq!(options.moves, 1..=usize::MAX);
+        assert_eq!(fewest_generated_moves(options), 1);
+    }
+
+    #[test]
+    fn boards_take_the_minimum_moves_without_allow_trivial() {
+        let options = generation_options_for(&["--min-moves", "3"]);
+        assert_eq!(options.moves, 3..=usize::MAX);
+        assert!(fewest_generated_moves(options) >= 3);
+    }
+
+    #[test]
+    fn default_board_solution_is_pinned() {
+        let state = State::from_grid(&default_grid()).expect("a valid board");
+        let solution = state
+            .solve_from_here()
+            .expect("the default board is solvable");
+
+        assert_eq!(solution.len() - 1, 12);
+        assert_eq!(
+            State::to_notation(&solution).expect("straight moves"),
+            "R2← R3← R2↓ R1→ R1↓ R2→ A↑ R2← R4← R2↑ A→ A↑"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use color_eyre::Result;
+use std::io::{stdin, Write};
+use std::ops::RangeInclusive;
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::{clear, cursor, style, terminal_size};
+
+const SIZES: [(&str, usize); 3] = [("small", 5), ("medium", 7), ("large", 10)];
+//the number of moves the optimal solution takes, which the generator can reach on every size
+const DIFFICULTIES: [(&str, usize, usize); 3] =
+    [("easy", 4, 5), ("medium", 6, 8), ("hard", 9, usize::MAX)];
+const TITLE: &str = "ASTRO AND ROBOTS";
+const HINT: &str = "arrows to choose, enter to start, esc to quit";
+
+/// the board size and difficulty picked from the startup menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Choice {
+    pub size: usize,
+    pub moves: RangeInclusive<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Size,
+    Difficulty,
+}
+
+/// shows the menu until a board is picked, or returns `None` if the player quits instead.
+/// expects the terminal to already be in raw mode.
+pub fn run(stdout: &mut impl Write) -> Result<Option<Choice>> {
+    let mut row = Row::Size;
+    let mut size = 0;
+    let mut difficulty = 0;
+
+    draw(stdout, row, size, difficulty)?;
+    for key in stdin().keys() {
+        //left and right wrap around, so that every option is one direction away
+        let cycle = |index: usize, len: usize, forward: bool| {
+            if forward {
+                (index + 1) % len
+            } else {
+                (index + len - 1) % len
+            }
+        };
+
+        match key? {
+            Key::Up | Key::Down => {
+                row = match row {
+                    Row::Size => Row::Difficulty,
+                    Row::Difficulty => Row::Size,
+                }
+            }
+            key @ (Key::Left | Key::Right) => {
+                let forward = key == Key::Right;
+                match row {
+                    Row::Size => size = cycle(size, SIZES.len(), forward),
+                    Row::Difficulty => difficulty = cycle(difficulty, DIFFICULTIES.len(), forward),
+                }
+            }
+            Key::Char('\n') => {
+                let (_, min_moves, max_moves) = DIFFICULTIES[difficulty];
+                return Ok(Some(Choice {
+                    size: SIZES[size].1,
+                    moves: min_moves..=max_moves,
+                }));
+            }
+            Key::Esc | Key::Ctrl('c') => break,
+            _ => continue,
+        }
+
+        draw(stdout, row, size, difficulty)?;
+    }
+
+    Ok(None)
+}
+
+fn draw(stdout: &mut impl Write, row: Row, size: usize, difficulty: usize) -> Result<()> {
+    let (name, dim) = SIZES[size];
+    let (difficulty, _, _) = DIFFICULTIES[difficulty];
+    let option = |label: &str, value: String, selected: bool| {
+        let value = format!("< {value} >");
+        if selected {
+            format!("{label:<12}{}{value}{}", style::Invert, style::NoInvert)
+        } else {
+            format!("{label:<12}{value}")
+        }
+    };
+
+    let lines = [
+        TITLE.to_string(),
+        String::new(),
+        option("size", format!("{name} {dim}x{dim}"), row == Row::Size),
+        option("difficulty", difficulty.to_string(), row == Row::Difficulty),
+        String::new(),
+        HINT.to_string(),
+    ];
+
+    //centered as a block, with the hint being the widest line
+    let (term_cols, term_rows) = terminal_size().unwrap_or((0, 0));
+    let col = (usize::from(term_cols).saturating_sub(HINT.len()) / 2).max(1);
+    let top = (usize::from(term_rows).saturating_sub(lines.len()) / 2).max(1);
+
+    write!(stdout, "{}", clear::All)?;
+    for (i, line) in lines.iter().enumerate() {
+        let goto = cursor::Goto(u16::try_from(col)?, u16::try_from(top + i)?);
+        write!(stdout, "{goto}{line}")?;
+    }
+
+    stdout.flush()?;
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use astro_and_robots::game::{self, Action, Area, Game, Mode};
+use astro_and_robots::state::{Direction, MovementAttempt};
+use color_eyre::Result;
+use std::io::{stdin, Write};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::{clear, color, cursor, style, terminal_size};
+
+const LABELS: [&str; 2] = ["PLAYER 1 (wasd, q/e)", "PLAYER 2 (arrows, ,/.)"];
+
+/// what a key does in the race, and for which player
+enum Input {
+    Move(usize, Direction),
+    PrevCharacter(usize),
+    NextCharacter(usize),
+    Exit,
+}
+
+fn input(key: Key) -> Option<Input> {
+    let input = match key {
+        Key::Char('w') => Input::Move(0, Direction::Up),
+        Key::Char('s') => Input::Move(0, Direction::Down),
+        Key::Char('a') => Input::Move(0, Direction::Left),
+        Key::Char('d') => Input::Move(0, Direction::Right),
+        Key::Char('q') => Input::PrevCharacter(0),
+        Key::Char('e') => Input::NextCharacter(0),
+
+        Key::Up => Input::Move(1, Direction::Up),
+        Key::Down => Input::Move(1, Direction::Down),
+        Key::Left => Input::Move(1, Direction::Left),
+        Key::Right => Input::Move(1, Direction::Right),
+        Key::Char(',') => Input::PrevCharacter(1),
+        Key::Char('.') => Input::NextCharacter(1),
+
+        Key::Esc | Key::Ctrl('c') => Input::Exit,
+        _ => return None,
+    };
+    Some(input)
+}
+
+/// lets two players play `games` side by side, until one of them reaches the goal and then exits.
+/// expects the terminal to already be in raw mode.
+pub fn run(mut games: [Game; 2], stdout: &mut impl Write) -> Result<()> {
+    let mut winner = None;
+
+    games[0].draw_intro(stdout)?;
+    let mut keys = stdin().keys();
+    if let Some(key) = keys.next() {
+        key?;
+    }
+
+    draw(&games, winner, stdout)?;
+    for key in keys {
+        let Some(input) = input(key?) else {
+            continue;
+        };
+
+        match input {
+            Input::Exit => break,
+            //the board stays up once someone has won, until the players exit
+            _ if winner.is_some() => continue,
+            Input::Move(player, direction) => {
+                let game = &mut games[player];
+                game.clear_status();
+                if let Action::Movement(MovementAttempt::Success(pos)) = game.move_toward(direction)
+                {
+                    game.move_selection_to(pos);
+                }
+                if game.mode() == Mode::GameOver {
+                    winner = Some(player);
+                }
+            }
+            Input::PrevCharacter(player) => games[player].select_prev_character(),
+            Input::NextCharacter(player) => games[player].select_next_character(),
+        }
+
+        draw(&games, winner, stdout)?;
+    }
+
+    //leave the shell prompt below the last drawn screen
+    writeln!(stdout, "\r")?;
+    Ok(())
+}
+
+/// draws each game in its own half of the terminal, with its player above it
+fn draw(games: &[Game; 2], winner: Option<usize>, stdout: &mut impl Write) -> Result<()> {
+    write!(stdout, "{}", clear::All)?;
+    let (cols, rows) = terminal_size()?;
+    let half = cols / 2;
+
+    for (i, (game, label)) in games.iter().zip(LABELS).enumerate() {
+        let col_offset = half * u16::try_from(i)?;
+        game.draw_in(
+            stdout,
+            Some(Area {
+                size: (half, rows),
+                col_offset,
+            }),
+        )?;
+        write!(stdout, "{}{label}", cursor::Goto(col_offset + 1, 1))?;
+    }
+
+    if let Some(winner) = winner {
+        let banner = format!("PLAYER {} WINS! press esc to quit", winner + 1);
+        let col = (usize::from(cols).saturating_sub(banner.len()) / 2).max(1);
+        write!(
+            stdout,
+            "{}{}",
+            cursor::Goto(u16::try_from(col)?, 3),
+            style::Bold
+        )?;
+        game::write_colored(stdout, banner, color::Green)?;
+        write!(stdout, "{}", style::Reset)?;
+    }
+
+    stdout.flush()?;
+    Ok(())
+}
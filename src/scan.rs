@@ -0,0 +1,65 @@
+use crate::bench::print_histogram;
+use astro_and_robots::state::{GenerationOptions, State};
+use color_eyre::Result;
+use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+//how many of the longest solution lengths have their seeds listed
+const HIGH_END_LENGTHS: usize = 3;
+
+/// generates and solves the `size`x`size` board of every seed in `seeds`, then prints a histogram
+/// of their optimal solution lengths, followed by the seeds of the longest ones.
+/// every line after the summary is stable, so that seeds can be picked out by a script.
+pub fn run(size: usize, seeds: Range<u64>, options: GenerationOptions) -> Result<()> {
+    let mut seeds_by_length = BTreeMap::<usize, Vec<u64>>::new();
+    let mut failures = 0;
+
+    for seed in seeds.clone() {
+        let options = GenerationOptions {
+            seed: Some(seed),
+            ..options.clone()
+        };
+        let generated = match State::generate(size, size, &options) {
+            Ok(generated) => generated,
+            Err(err) => {
+                failures += 1;
+                println!("seed {seed}: {err:#}");
+                continue;
+            }
+        };
+        let solution = generated
+            .state
+            .solve_from_here()
+            .expect("generated states are solvable");
+        seeds_by_length
+            .entry(solution.len() - 1)
+            .or_default()
+            .push(seed);
+    }
+
+    let solved = seeds_by_length.values().map(Vec::len).sum::<usize>();
+    println!(
+        "{size}x{size}, seeds {}..{}: {solved} boards solved, {failures} failed",
+        seeds.start, seeds.end
+    );
+    if solved == 0 {
+        return Ok(());
+    }
+
+    println!();
+    println!("optimal solution lengths (moves):");
+    print_histogram(
+        seeds_by_length
+            .iter()
+            .flat_map(|(&length, seeds)| itertools::repeat_n(length, seeds.len())),
+    );
+
+    println!();
+    println!("seeds of the longest solutions:");
+    for (length, seeds) in seeds_by_length.iter().rev().take(HIGH_END_LENGTHS) {
+        println!("{length:>4} | {}", seeds.iter().join(" "));
+    }
+
+    Ok(())
+}
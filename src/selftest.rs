@@ -0,0 +1,53 @@
+use astro_and_robots::state::{GenerationOptions, State};
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use itertools::Itertools;
+use std::ops::RangeInclusive;
+
+/// generates a board of every size in `sizes` for each of the first `seeds` seeds,
+/// and checks that each one is solvable within the moves that `options` asks for.
+/// prints every failure, and fails if there were any.
+pub fn run(sizes: RangeInclusive<usize>, seeds: u64, options: GenerationOptions) -> Result<()> {
+    let mut failures = 0;
+    let mut total = 0;
+
+    for (rows, cols) in sizes.clone().cartesian_product(sizes) {
+        for seed in 0..seeds {
+            total += 1;
+            let options = GenerationOptions {
+                seed: Some(seed),
+                ..options.clone()
+            };
+
+            if let Err(err) = check(rows, cols, &options) {
+                failures += 1;
+                println!("{rows}x{cols}, seed {seed}: {err:#}");
+            }
+        }
+        println!("{rows}x{cols}: done");
+    }
+
+    println!("{} of {total} boards passed", total - failures);
+    if failures > 0 {
+        return Err(eyre!("{failures} of {total} boards failed"));
+    }
+    Ok(())
+}
+
+fn check(rows: usize, cols: usize, options: &GenerationOptions) -> Result<()> {
+    let state = State::generate(rows, cols, options)?.state;
+    //generation is what's being checked, so its result is solved again rather than trusted
+    let solution = state
+        .solve_from_here()
+        .ok_or_else(|| eyre!("unsolvable: {}", state.to_debug_line()))?;
+
+    let moves = solution.len() - 1;
+    if !options.moves.contains(&moves) {
+        return Err(eyre!(
+            "solved in {moves} moves, outside of {:?}: {}",
+            options.moves,
+            state.to_debug_line()
+        ));
+    }
+    Ok(())
+}
@@ -1,10 +1,19 @@
+use crate::error::GameError;
+use crate::game::solver;
 use color_eyre::eyre::{ensure, eyre, Context};
 use color_eyre::{Report, Result};
 use itertools::Itertools;
 use nanorand::{Rng, WyRand};
 use simple_grid::{Grid, GridIndex};
+use std::cmp::Ordering;
 use std::fmt::Display;
-use std::{cmp, iter};
+use std::iter;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use std::str::FromStr;
+
+//boards with up to this many tiles keep a bitset of where the robots are, see `State::robot_tiles`
+const INDEXED_MAX_TILES: usize = u128::BITS as usize;
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, PartialOrd, Ord)]
 pub struct Pos {
@@ -19,26 +28,71 @@ impl Display for Pos {
     }
 }
 
+/// parses a position the way it's displayed, as `(x, y)`
+impl FromStr for Pos {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (x, y) = s
+            .trim()
+            .strip_prefix('(')
+            .and_then(|s| s.strip_suffix(')'))
+            .and_then(|s| s.split_once(','))
+            .ok_or_else(|| eyre!("`{s}` is not a position"))?;
+
+        Ok(Pos {
+            x: x.trim()
+                .parse()
+                .wrap_err_with(|| format!("bad x in `{s}`"))?,
+            y: y.trim()
+                .parse()
+                .wrap_err_with(|| format!("bad y in `{s}`"))?,
+        })
+    }
+}
+
 impl From<Pos> for GridIndex {
     fn from(Pos { x, y }: Pos) -> Self {
         GridIndex::new(x, y)
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
 pub enum Selection {
     Astro,
+    //the second astro, on cooperative boards that have one
+    Partner,
     Robot(usize), //INVARIANT: n < num_robots. this is not checked.
 }
 
 // pub struct Grid<const ROWS: usize, const COLS: usize>(pub [[Tile; ROWS]; COLS]);
 
 impl Pos {
-    fn from_iter_pair(
-        xs: impl IntoIterator<Item = usize>,
-        ys: impl IntoIterator<Item = usize>,
-    ) -> Box<impl Iterator<Item = Pos>> {
-        iter::zip(xs, ys).map(Pos::from).into()
+    pub fn manhattan_distance(self, other: Pos) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    /// the position `dx` columns and `dy` rows away, or `None` if it's outside a grid of `dims` (rows, cols)
+    pub fn checked_offset(self, dx: isize, dy: isize, dims: (usize, usize)) -> Option<Pos> {
+        let (rows, cols) = dims;
+        let x = self.x.checked_add_signed(dx).filter(|&x| x < cols)?;
+        let y = self.y.checked_add_signed(dy).filter(|&y| y < rows)?;
+        Some(Pos { x, y })
+    }
+
+    /// the adjacent position in `direction`, or `None` if that's past the edge of a grid of `dims` (rows, cols)
+    pub fn step(self, direction: Direction, dims: (usize, usize)) -> Option<Pos> {
+        let (dx, dy) = match direction {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            Direction::UpLeft => (-1, -1),
+            Direction::UpRight => (1, -1),
+            Direction::DownLeft => (-1, 1),
+            Direction::DownRight => (1, 1),
+        };
+        self.checked_offset(dx, dy, dims)
     }
 }
 
@@ -58,8 +112,10 @@ impl From<[usize; 2]> for Pos {
 pub enum Tile {
     Empty,
     Astro,
+    Partner,
     Robot,
     Goal,
+    Blocker,
 }
 
 impl Display for Tile {
@@ -67,19 +123,161 @@ impl Display for Tile {
         let c = match self {
             Tile::Empty => '.',
             Tile::Astro => 'A',
+            Tile::Partner => 'a',
             Tile::Robot => 'R',
             Tile::Goal => 'X',
+            Tile::Blocker => 'B',
         };
         write!(f, "{c}")
     }
 }
 
-#[derive(Clone)]
+impl TryFrom<char> for Tile {
+    type Error = GameError;
+
+    fn try_from(c: char) -> Result<Tile, GameError> {
+        let tile = match c {
+            '.' => Tile::Empty,
+            'A' => Tile::Astro,
+            'a' => Tile::Partner,
+            'R' => Tile::Robot,
+            'X' => Tile::Goal,
+            'B' => Tile::Blocker,
+            _ => return Err(GameError::UnknownTile(c)),
+        };
+        Ok(tile)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Direction {
     Up,
     Down,
     Left,
     Right,
+    //only with `Movement::EightWay`
+    UpLeft,
+    UpRight,
+    DownLeft,
+    DownRight,
+}
+
+impl Direction {
+    /// the four orthogonal directions, in the order the solver tries them
+    pub const fn all() -> [Direction; 4] {
+        [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ]
+    }
+
+    /// the direction that undoes a step in this one
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::UpLeft => Direction::DownRight,
+            Direction::UpRight => Direction::DownLeft,
+            Direction::DownLeft => Direction::UpRight,
+            Direction::DownRight => Direction::UpLeft,
+        }
+    }
+
+    /// the arrow that stands for this direction in move notation
+    pub fn arrow(self) -> char {
+        match self {
+            Direction::Up => '↑',
+            Direction::Down => '↓',
+            Direction::Left => '←',
+            Direction::Right => '→',
+            Direction::UpLeft => '↖',
+            Direction::UpRight => '↗',
+            Direction::DownLeft => '↙',
+            Direction::DownRight => '↘',
+        }
+    }
+
+    pub fn from_arrow(arrow: char) -> Option<Direction> {
+        Movement::EightWay
+            .directions()
+            .iter()
+            .copied()
+            .find(|direction| direction.arrow() == arrow)
+    }
+}
+
+/// which directions pieces can slide in.
+#[derive(Clone, Copy, Hash, Debug, Default, Eq, PartialEq)]
+pub enum Movement {
+    #[default]
+    Orthogonal,
+    //diagonally as well, stopping at whichever edge a diagonal path reaches first
+    EightWay,
+}
+
+impl Movement {
+    pub fn directions(self) -> &'static [Direction] {
+        const ORTHOGONAL: [Direction; 4] = Direction::all();
+        const EIGHT_WAY: [Direction; 8] = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+            Direction::UpLeft,
+            Direction::UpRight,
+            Direction::DownLeft,
+            Direction::DownRight,
+        ];
+
+        match self {
+            Movement::Orthogonal => &ORTHOGONAL,
+            Movement::EightWay => &EIGHT_WAY,
+        }
+    }
+}
+
+/// the positions along a path in one direction, nearest first.
+#[derive(Debug, Clone)]
+pub struct PathIter {
+    //the last position returned, or the start of the path
+    pos: Pos,
+    direction: Direction,
+    dims: (usize, usize),
+}
+
+impl Iterator for PathIter {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        self.pos = self.pos.step(self.direction, self.dims)?;
+        Some(self.pos)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (rows, cols) = self.dims;
+        let Pos { x, y } = self.pos;
+
+        let up = y;
+        let down = rows.saturating_sub(y + 1);
+        let left = x;
+        let right = cols.saturating_sub(x + 1);
+        //a diagonal path ends at whichever edge it reaches first
+        let remaining = match self.direction {
+            Direction::Up => up,
+            Direction::Down => down,
+            Direction::Left => left,
+            Direction::Right => right,
+            Direction::UpLeft => up.min(left),
+            Direction::UpRight => up.min(right),
+            Direction::DownLeft => down.min(left),
+            Direction::DownRight => down.min(right),
+        };
+        (remaining, Some(remaining))
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -91,20 +289,376 @@ pub enum MovementAttempt {
 #[derive(Clone, Hash, Debug, Eq, PartialEq)]
 pub struct State {
     pub astro: Pos,
-    pub robots: Vec<Pos>,
-    invariants: Invariants,
+    //a second astro that has to reach a goal of its own, on cooperative boards
+    partner: Option<Pos>,
+    //only changed through `set_pos`, so that `robot_tiles` stays in step with it
+    robots: Vec<Pos>,
+    //a bit for each tile with a robot on it, row by row, so that `tile_at` doesn't search `robots`.
+    //always empty on boards with more than `INDEXED_MAX_TILES` tiles, which do search it
+    robot_tiles: u128,
+    //shared between a state and everything reached from it, so that moving a piece only copies positions
+    invariants: Rc<Invariants>,
+}
+
+/// how `State::generate` searches for a board.
+#[derive(Clone, Debug)]
+pub struct GenerationOptions {
+    /// `None` picks a different board every time
+    pub seed: Option<u64>,
+    /// how many candidates are tried before the budget is raised
+    pub attempts: usize,
+    /// how many candidates are tried in total before giving up
+    pub max_attempts: usize,
+    /// with `WinCondition::AllPieces`, there's a goal for every piece
+    pub win_condition: WinCondition,
+    /// boards with fewer robots tend to be plain sliding puzzles
+    pub min_robots: usize,
+    pub stop_rule: StopRule,
+    pub collision_rule: CollisionRule,
+    pub movement: Movement,
+    /// when set, each robot can only be moved this many times
+    pub robot_moves: Option<usize>,
+    /// how many moves the optimal solution may take
+    pub moves: RangeInclusive<usize>,
+    pub placement: Placement,
+    /// adds a second astro, which needs a goal of its own
+    pub partner: bool,
+    /// only accepts boards that can't be solved without moving a robot
+    pub require_robot_moves: bool,
+}
+
+/// where `State::generate` puts the robots of a candidate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Placement {
+    #[default]
+    Uniform,
+    //mostly in or next to the rows and columns of the astro and the goal, where they get in the way.
+    //robots far from them tend to be irrelevant to the solution
+    NearRoute,
+}
+
+impl Default for GenerationOptions {
+    fn default() -> Self {
+        GenerationOptions {
+            seed: None,
+            attempts: 5000,
+            max_attempts: 40000,
+            win_condition: WinCondition::AstroOnly,
+            min_robots: 0,
+            stop_rule: StopRule::Adjacent,
+            collision_rule: CollisionRule::Stop,
+            movement: Movement::Orthogonal,
+            robot_moves: None,
+            moves: 4..=usize::MAX,
+            placement: Placement::Uniform,
+            partner: false,
+            require_robot_moves: false,
+        }
+    }
+}
+
+/// a generated state, along with how many candidates it took to find it.
+#[derive(Clone, Debug)]
+pub struct Generated {
+    pub state: State,
+    pub attempts: usize,
+}
+
+/// a board parsed from the text level format, along with what its comments say about it.
+#[derive(Clone, Debug)]
+pub struct Level {
+    pub state: State,
+    //from a `# name: ...` comment before the grid
+    pub name: Option<String>,
+}
+
+/// what the JSON level format says about a board besides its grid, all of it optional.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LevelMeta {
+    pub name: Option<String>,
+    pub author: Option<String>,
+    /// how hard the author meant the board to be, from 1 to 5 stars as `solver::difficulty_stars` rates it
+    pub difficulty: Option<u8>,
+    /// the number of moves the optimal solution takes, as stored, which `verify_par` checks
+    pub par: Option<usize>,
+}
+
+impl LevelMeta {
+    /// fails if the stored par isn't what solving `state` gives. a level without a par passes
+    pub fn verify_par(&self, state: &State) -> Result<()> {
+        let Some(par) = self.par else {
+            return Ok(());
+        };
+        match state.solve_from_here() {
+            Some(solution) if solution.len() - 1 == par => Ok(()),
+            Some(solution) => Err(eyre!(
+                "the stored par is {par}, but the optimal solution takes {} moves",
+                solution.len() - 1
+            )),
+            None => Err(eyre!(
+                "the stored par is {par}, but the board can't be solved"
+            )),
+        }
+    }
+}
+
+/// which pieces need to be on a goal for the board to be solved.
+#[derive(Clone, Copy, Hash, Debug, Default, Eq, PartialEq)]
+pub enum WinCondition {
+    #[default]
+    AstroOnly,
+    //astro and every robot, each on a different goal
+    AllPieces,
+    //a robot rather than the astro
+    Occupant(GoalOccupant),
+}
+
+/// which robot has to reach a goal under `WinCondition::Occupant`.
+#[derive(Clone, Copy, Hash, Debug, Eq, PartialEq)]
+pub enum GoalOccupant {
+    AnyRobot,
+    //INVARIANT: n < num_robots, checked by `with_win_condition`
+    Robot(usize),
+}
+
+/// where a sliding piece is allowed to come to a stop.
+#[derive(Clone, Copy, Hash, Debug, Default, Eq, PartialEq)]
+pub enum StopRule {
+    //pieces only stop right next to another piece, whether or not that's on a goal
+    #[default]
+    Adjacent,
+    //pieces also stop on any goal they slide onto
+    OnGoal,
+    //robots can't stop on a goal, so only the astro can ever park there
+    RobotsAvoidGoal,
+}
+
+/// what happens when a sliding piece runs into a robot.
+#[derive(Clone, Copy, Hash, Debug, Default, Eq, PartialEq)]
+pub enum CollisionRule {
+    //the piece stops right before the robot
+    #[default]
+    Stop,
+    //the piece pushes the robot, which slides on until it stops, and stops right behind it.
+    //a robot that nothing would stop can't be pushed
+    Push,
 }
 
 #[derive(Clone, Hash, Debug, Eq, PartialEq)]
 pub struct Invariants {
-    goal: Pos,
+    //never empty
+    goals: Vec<Pos>,
+    win_condition: WinCondition,
+    stop_rule: StopRule,
+    collision_rule: CollisionRule,
+    movement: Movement,
+    //robots that can never be moved
+    blockers: Vec<Pos>,
     rows: usize,
     cols: usize,
 }
 
 impl State {
+    /// builds a state from explicit positions, checking that every position is on the grid
+    /// and that no two pieces (or a piece and the goal) share a position.
+    pub fn new(
+        astro: Pos,
+        goal: Pos,
+        robots: Vec<Pos>,
+        rows: usize,
+        cols: usize,
+    ) -> Result<State, GameError> {
+        let in_bounds = |piece: &'static str, pos: Pos| {
+            if pos.x < cols && pos.y < rows {
+                Ok(())
+            } else {
+                Err(GameError::OutOfBounds { piece, pos })
+            }
+        };
+        let apart = |first: &'static str, second: &'static str, pos: Pos, other: Pos| {
+            if pos == other {
+                Err(GameError::Overlap { first, second, pos })
+            } else {
+                Ok(())
+            }
+        };
+
+        in_bounds("player", astro)?;
+        in_bounds("goal", goal)?;
+        apart("player", "goal", astro, goal)?;
+
+        for (i, &robot) in robots.iter().enumerate() {
+            in_bounds("robot", robot)?;
+            apart("robot", "player", robot, astro)?;
+            apart("robot", "goal", robot, goal)?;
+            if robots[..i].contains(&robot) {
+                return Err(GameError::Overlap {
+                    first: "robot",
+                    second: "robot",
+                    pos: robot,
+                });
+            }
+        }
+
+        let state = State::from_parts(
+            astro,
+            None,
+            robots,
+            Invariants {
+                goals: vec![goal],
+                win_condition: WinCondition::AstroOnly,
+                stop_rule: StopRule::Adjacent,
+                collision_rule: CollisionRule::Stop,
+                movement: Movement::Orthogonal,
+                blockers: Vec::new(),
+                rows,
+                cols,
+            },
+        );
+        Ok(state)
+    }
+
+    /// adds a second astro at `partner`, which has to reach a goal other than the astro's.
+    /// fails if `partner` is off the grid or taken, including by a goal.
+    pub fn with_partner(mut self, partner: Pos) -> Result<State> {
+        ensure!(self.partner.is_none(), "there is already a second player");
+        let (rows, cols) = self.dims();
+        ensure!(
+            partner.x < cols && partner.y < rows,
+            "second player at {partner} is out of bounds"
+        );
+        ensure!(
+            self.tile_at(partner) == Tile::Empty,
+            "second player at {partner} overlaps another tile"
+        );
+
+        self.partner = Some(partner);
+        Ok(self)
+    }
+
+    /// the second astro, on cooperative boards
+    pub fn partner(&self) -> Option<Pos> {
+        self.partner
+    }
+
+    /// the astro, followed by the second astro if there is one
+    pub fn astros(&self) -> impl Iterator<Item = Pos> {
+        iter::once(self.astro).chain(self.partner)
+    }
+
+    /// every piece that can be moved: the astros, followed by every robot in order
+    pub fn selections(&self) -> impl Iterator<Item = Selection> {
+        let partner = self.partner.map(|_| Selection::Partner);
+        let robots = (0..self.num_robots()).map(Selection::Robot);
+        iter::once(Selection::Astro).chain(partner).chain(robots)
+    }
+
+    /// how many pieces `selections` yields
+    pub fn num_selections(&self) -> usize {
+        self.astros().count() + self.num_robots()
+    }
+
+    /// requires every piece to reach a goal under `WinCondition::AllPieces`,
+    /// which needs at least as many goals as there are pieces.
+    /// `WinCondition::Occupant` needs the robot it names to exist.
+    pub fn with_win_condition(mut self, win_condition: WinCondition) -> Result<State> {
+        match win_condition {
+            WinCondition::AstroOnly => (),
+            WinCondition::AllPieces => {
+                let pieces = self.num_selections();
+                let goals = self.num_goals();
+                ensure!(
+                    goals >= pieces,
+                    "every piece needs a goal, but there are {pieces} pieces and {goals} goals"
+                );
+            }
+            WinCondition::Occupant(GoalOccupant::AnyRobot) => {
+                ensure!(
+                    self.num_robots() > 0,
+                    "a robot has to reach the goal, but there are none"
+                );
+            }
+            WinCondition::Occupant(GoalOccupant::Robot(n)) => {
+                let robots = self.num_robots();
+                ensure!(
+                    n < robots,
+                    "robot {} has to reach the goal, but there are only {robots} robots",
+                    n + 1
+                );
+            }
+        }
+
+        Rc::make_mut(&mut self.invariants).win_condition = win_condition;
+        Ok(self)
+    }
+
+    pub fn with_stop_rule(mut self, stop_rule: StopRule) -> State {
+        Rc::make_mut(&mut self.invariants).stop_rule = stop_rule;
+        self
+    }
+
+    pub fn stop_rule(&self) -> StopRule {
+        self.invariants.stop_rule
+    }
+
+    pub fn with_collision_rule(mut self, collision_rule: CollisionRule) -> State {
+        Rc::make_mut(&mut self.invariants).collision_rule = collision_rule;
+        self
+    }
+
+    pub fn collision_rule(&self) -> CollisionRule {
+        self.invariants.collision_rule
+    }
+
+    pub fn with_movement(mut self, movement: Movement) -> State {
+        Rc::make_mut(&mut self.invariants).movement = movement;
+        self
+    }
+
+    pub fn movement(&self) -> Movement {
+        self.invariants.movement
+    }
+
+    pub fn win_condition(&self) -> WinCondition {
+        self.invariants.win_condition
+    }
+
+    /// every goal on the board, of which there is always at least one
+    pub fn goal_positions(&self) -> &[Pos] {
+        &self.invariants.goals
+    }
+
+    pub fn num_goals(&self) -> usize {
+        self.invariants.goals.len()
+    }
+
     pub fn is_at_goal(&self) -> bool {
-        self.astro == self.invariants.goal
+        let goals = self.goal_positions();
+        match self.invariants.win_condition {
+            WinCondition::AstroOnly => self.astros().all(|astro| goals.contains(&astro)),
+            WinCondition::AllPieces => self
+                .astros()
+                .chain(self.robots.iter().copied())
+                .all(|piece| goals.contains(&piece)),
+            WinCondition::Occupant(GoalOccupant::AnyRobot) => {
+                self.robots.iter().any(|robot| goals.contains(robot))
+            }
+            WinCondition::Occupant(GoalOccupant::Robot(n)) => goals.contains(&self.robots[n]),
+        }
+    }
+
+    /// the grid distance from astro to the nearest goal, ignoring anything in the way.
+    /// with a second astro, the distances of both are added up
+    pub fn manhattan_to_goal(&self) -> usize {
+        self.astros()
+            .map(|astro| {
+                self.goal_positions()
+                    .iter()
+                    .map(|&goal| astro.manhattan_distance(goal))
+                    .min()
+                    .expect("there is always a goal")
+            })
+            .sum()
     }
 
     pub fn dims(&self) -> (usize, usize) {
@@ -116,9 +670,13 @@ impl State {
         //which means astro and robots will draw over the goal.
         if self.astro == pos {
             Tile::Astro
-        } else if self.robots.contains(&pos) {
+        } else if self.partner == Some(pos) {
+            Tile::Partner
+        } else if self.has_robot(pos) {
             Tile::Robot
-        } else if self.invariants.goal == pos {
+        } else if self.invariants.blockers.contains(&pos) {
+            Tile::Blocker
+        } else if self.goal_positions().contains(&pos) {
             Tile::Goal
         } else {
             Tile::Empty
@@ -128,39 +686,194 @@ impl State {
     pub fn pos_of(&self, selection: Selection) -> Pos {
         match selection {
             Selection::Astro => self.astro,
+            Selection::Partner => self
+                .partner
+                .expect("only selected on boards with a partner"),
             Selection::Robot(n) => self.robots[n],
         }
     }
 
-    pub fn pos_of_mut(&mut self, selection: Selection) -> &mut Pos {
+    /// the piece standing on `pos`, if any
+    pub fn selection_at(&self, pos: Pos) -> Option<Selection> {
+        if self.astro == pos {
+            Some(Selection::Astro)
+        } else if self.partner == Some(pos) {
+            Some(Selection::Partner)
+        } else if !self.has_robot(pos) {
+            None
+        } else {
+            self.robots
+                .iter()
+                .position(|&robot| robot == pos)
+                .map(Selection::Robot)
+        }
+    }
+
+    /// why the piece `selection` can't be put at `pos`: off the grid, on a blocker or on another piece.
+    /// its own position is fine, so that putting a piece back where it is isn't an error.
+    pub fn check_place(&self, selection: Selection, pos: Pos) -> Result<(), GameError> {
+        let name = |selection: Selection| match selection {
+            Selection::Astro => "player",
+            Selection::Partner => "second player",
+            Selection::Robot(_) => "robot",
+        };
+        let piece = name(selection);
+
+        let (rows, cols) = self.dims();
+        if pos.x >= cols || pos.y >= rows {
+            return Err(GameError::OutOfBounds { piece, pos });
+        }
+        if self.invariants.blockers.contains(&pos) {
+            return Err(GameError::Overlap {
+                first: piece,
+                second: "blocker",
+                pos,
+            });
+        }
+        match self.selection_at(pos) {
+            Some(other) if other != selection => Err(GameError::Overlap {
+                first: piece,
+                second: name(other),
+                pos,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// moves the piece `selection` to `pos`, unless `check_place` rejects it
+    pub fn try_place(&mut self, selection: Selection, pos: Pos) -> Result<(), GameError> {
+        self.check_place(selection, pos)?;
+        self.set_pos(selection, pos);
+        Ok(())
+    }
+
+    /// like `try_place`, but for a piece sliding to `pos` as `move_toward` found it would,
+    /// so that under `CollisionRule::Push` the robots it pushes are moved along with it
+    pub fn try_move(&mut self, selection: Selection, pos: Pos) -> Result<(), GameError> {
+        let from = self.pos_of(selection);
+        let push = PosChange(from, pos)
+            .direction()
+            .and_then(|direction| self.push_toward(from, direction));
+
+        //the pushed robot moves first, since the piece may end up where it was
+        if let Some((_, PosChange(robot_from, robot_to))) = push.filter(|&(to, _)| to == pos) {
+            let robot = self
+                .selection_at(robot_from)
+                .expect("only robots are pushed");
+            self.try_place(robot, robot_to)?;
+        }
+        self.try_place(selection, pos)
+    }
+
+    /// moves the piece `selection` to `pos` without checking that it's free, unlike `try_place`
+    pub fn set_pos(&mut self, selection: Selection, pos: Pos) {
         match selection {
-            Selection::Astro => &mut self.astro,
-            Selection::Robot(n) => &mut self.robots[n],
+            Selection::Astro => self.astro = pos,
+            Selection::Partner => self.partner = Some(pos),
+            Selection::Robot(n) => {
+                let from = self.robots[n];
+                self.robot_tiles &= !self.tile_bit(from);
+                self.robot_tiles |= self.tile_bit(pos);
+                self.robots[n] = pos;
+            }
         }
     }
 
+    pub fn robots(&self) -> &[Pos] {
+        &self.robots
+    }
+
     pub fn num_robots(&self) -> usize {
         self.robots.len()
     }
 
+    fn from_parts(
+        astro: Pos,
+        partner: Option<Pos>,
+        robots: Vec<Pos>,
+        invariants: Invariants,
+    ) -> State {
+        let mut state = State {
+            astro,
+            partner,
+            robots,
+            robot_tiles: 0,
+            invariants: Rc::new(invariants),
+        };
+        state.robot_tiles = state
+            .robots
+            .iter()
+            .fold(0, |tiles, &robot| tiles | state.tile_bit(robot));
+        state
+    }
+
+    /// the bit for `pos` in `robot_tiles`, or no bit at all on boards too large to keep it
+    fn tile_bit(&self, pos: Pos) -> u128 {
+        let (rows, cols) = self.dims();
+        if rows * cols > INDEXED_MAX_TILES || pos.x >= cols || pos.y >= rows {
+            return 0;
+        }
+        1 << (pos.y * cols + pos.x)
+    }
+
+    fn has_robot(&self, pos: Pos) -> bool {
+        let (rows, cols) = self.dims();
+        if rows * cols > INDEXED_MAX_TILES {
+            return self.robots.contains(&pos);
+        }
+        self.robot_tiles & self.tile_bit(pos) != 0
+    }
+
+    /// slides the piece at `current_pos` until it's right next to another piece,
+    /// or until it's stopped by a goal, depending on the `StopRule`.
+    ///
+    /// fails if the piece is already against the edge or another piece in `direction`,
+    /// or if nothing would stop it before it slides off the grid.
+    ///
+    /// under `CollisionRule::Push`, a piece that runs into a robot pushes it instead, as `push_toward` describes.
     pub fn move_toward(&self, current_pos: Pos, direction: Direction) -> MovementAttempt {
+        match self.push_toward(current_pos, direction) {
+            Some((pos, _)) => MovementAttempt::Success(pos),
+            None => self.slide_toward(current_pos, direction),
+        }
+    }
+
+    /// `move_toward` under `CollisionRule::Stop`, which is also how pushed robots slide
+    fn slide_toward(&self, current_pos: Pos, direction: Direction) -> MovementAttempt {
         let mut path = self.positions_in_path(current_pos, direction).peekable();
+        let stop_rule = self.invariants.stop_rule;
+        let is_robot = matches!(self.selection_at(current_pos), Some(Selection::Robot(_)));
 
         loop {
             //if the end of the path was reached
-            let Some(pos) = path.next() else { break MovementAttempt::Failure };
+            let Some(pos) = path.next() else {
+                break MovementAttempt::Failure;
+            };
 
             match self.tile_at(pos) {
                 //if reached a tile that can't be stopped on,
                 //and also couldn't stop on previous tile
-                Tile::Robot | Tile::Astro => break MovementAttempt::Failure,
+                Tile::Robot | Tile::Astro | Tile::Partner | Tile::Blocker => {
+                    break MovementAttempt::Failure
+                }
 
                 //if reached a tile that can be stopped on
-                Tile::Empty | Tile::Goal => {
+                tile @ (Tile::Empty | Tile::Goal) => {
+                    let on_goal = tile == Tile::Goal;
+                    if on_goal && stop_rule == StopRule::OnGoal {
+                        break MovementAttempt::Success(pos);
+                    }
+
                     let next_tile = path.peek().map(|&pos| self.tile_at(pos));
 
                     //...but the next tile can't be stopped on
-                    if let Some(Tile::Robot | Tile::Astro) = next_tile {
+                    if let Some(Tile::Robot | Tile::Astro | Tile::Partner | Tile::Blocker) =
+                        next_tile
+                    {
+                        //a robot that may not stop on the goal can't go any further either
+                        if on_goal && is_robot && stop_rule == StopRule::RobotsAvoidGoal {
+                            break MovementAttempt::Failure;
+                        }
                         break MovementAttempt::Success(pos);
                     }
 
@@ -170,151 +883,1055 @@ impl State {
         }
     }
 
-    fn positions_in_path(
-        &self,
-        path_start: Pos,
-        movement_direction: Direction,
-    ) -> Box<dyn Iterator<Item = Pos>> {
-        let Pos { x, y } = path_start;
+    /// under `CollisionRule::Push`, where the piece at `current_pos` ends up when it slides in `direction`
+    /// and runs into a robot, along with how that robot moves. the robot slides on as it would if it
+    /// were moved itself, and the piece stops right behind it.
+    ///
+    /// `None` under `CollisionRule::Stop`, or when the piece doesn't run into a robot that can be pushed,
+    /// such as one that nothing would stop before the edge of the grid. the piece then stops as usual.
+    pub fn push_toward(&self, current_pos: Pos, direction: Direction) -> Option<(Pos, PosChange)> {
+        if self.collision_rule() == CollisionRule::Stop {
+            return None;
+        }
 
-        match movement_direction {
-            Direction::Up => {
-                let xs = iter::repeat(x);
-                let ys = (0..y).rev();
-                Pos::from_iter_pair(xs, ys)
-            }
-            Direction::Down => {
-                let xs = iter::repeat(x);
-                let ys = y + 1..self.invariants.rows;
-                Pos::from_iter_pair(xs, ys)
-            }
-            Direction::Left => {
-                let xs = (0..x).rev();
-                let ys = iter::repeat(y);
-                Pos::from_iter_pair(xs, ys)
-            }
-            Direction::Right => {
-                let xs = x + 1..self.invariants.cols;
-                let ys = iter::repeat(y);
-                Pos::from_iter_pair(xs, ys)
+        let contact = self
+            .positions_in_path(current_pos, direction)
+            .find(|&pos| !matches!(self.tile_at(pos), Tile::Empty | Tile::Goal))?;
+        if self.tile_at(contact) != Tile::Robot {
+            return None;
+        }
+        //a piece that stops on goals never reaches a robot past one
+        let stop_rule = self.stop_rule();
+        let passes_goal = self
+            .positions_in_path(current_pos, direction)
+            .take_while(|&pos| pos != contact)
+            .any(|pos| self.tile_at(pos) == Tile::Goal);
+        if passes_goal && stop_rule == StopRule::OnGoal {
+            return None;
+        }
+
+        let MovementAttempt::Success(robot_to) = self.slide_toward(contact, direction) else {
+            return None;
+        };
+        let pos = robot_to
+            .step(direction.opposite(), self.dims())
+            .expect("the pushed robot moved away from the piece");
+
+        let is_robot = matches!(self.selection_at(current_pos), Some(Selection::Robot(_)));
+        if is_robot
+            && stop_rule == StopRule::RobotsAvoidGoal
+            && self.goal_positions().contains(&pos)
+        {
+            return None;
+        }
+
+        Some((pos, PosChange(contact, robot_to)))
+    }
+
+    /// like `move_toward`, but only ever one tile, rather than sliding until an obstacle.
+    /// fails at the edge of the board, or when the next tile is taken.
+    pub fn step_toward(&self, current_pos: Pos, direction: Direction) -> MovementAttempt {
+        match current_pos.step(direction, self.dims()) {
+            Some(pos) if matches!(self.tile_at(pos), Tile::Empty | Tile::Goal) => {
+                MovementAttempt::Success(pos)
             }
+            _ => MovementAttempt::Failure,
+        }
+    }
+
+    /// the first piece or blocker in each direction from `pos`, in the order up, down, left, right.
+    /// `None` means there's nothing in that direction before the edge of the grid.
+    pub fn blockers_around(&self, pos: Pos) -> [Option<Tile>; 4] {
+        Direction::all().map(|direction| {
+            self.positions_in_path(pos, direction)
+                .map(|pos| self.tile_at(pos))
+                .find(|tile| {
+                    matches!(
+                        tile,
+                        Tile::Robot | Tile::Astro | Tile::Partner | Tile::Blocker
+                    )
+                })
+        })
+    }
+
+    /// the positions a piece starting at `path_start` passes through when moving in `movement_direction`,
+    /// up to the edge of the grid and regardless of what's on them.
+    pub fn positions_in_path(&self, path_start: Pos, movement_direction: Direction) -> PathIter {
+        PathIter {
+            pos: path_start,
+            direction: movement_direction,
+            dims: self.dims(),
         }
     }
 
-    pub fn from_grid(grid: &Grid<Tile>) -> Result<State> {
+    pub fn from_grid(grid: &Grid<Tile>) -> Result<State, GameError> {
         let mut astro = None;
-        let mut goal = None;
+        let mut partner = None;
+        let mut goals = Vec::new();
         let mut robots = Vec::new();
+        let mut blockers = Vec::new();
 
         let (cols, rows) = grid.dimensions();
         for pos in (0..cols).cartesian_product(0..rows).map(Pos::from) {
             match grid[pos] {
                 Tile::Empty => (),
                 Tile::Astro => {
-                    ensure!(astro.is_none(), "more than one player");
+                    if astro.is_some() {
+                        return Err(GameError::MultiplePlayers);
+                    }
                     astro = Some(pos);
                 }
-                Tile::Robot => robots.push(pos),
-                Tile::Goal => {
-                    ensure!(goal.is_none(), "more than one goal");
-                    goal = Some(pos);
+                Tile::Partner => {
+                    if partner.is_some() {
+                        return Err(GameError::MultiplePlayers);
+                    }
+                    partner = Some(pos);
                 }
+                Tile::Robot => robots.push(pos),
+                Tile::Blocker => blockers.push(pos),
+                Tile::Goal => goals.push(pos),
             }
         }
 
-        let astro = astro.ok_or_else(|| eyre!("no player"))?;
-        let goal = goal.ok_or_else(|| eyre!("no goal"))?;
-        let initial_state = State {
+        let astro = astro.ok_or(GameError::NoPlayer)?;
+        if goals.is_empty() {
+            return Err(GameError::NoGoal);
+        }
+        let initial_state = State::from_parts(
             astro,
+            partner,
             robots,
-            invariants: Invariants { goal, rows, cols },
-        };
+            Invariants {
+                goals,
+                win_condition: WinCondition::AstroOnly,
+                stop_rule: StopRule::Adjacent,
+                collision_rule: CollisionRule::Stop,
+                movement: Movement::Orthogonal,
+                blockers,
+                rows,
+                cols,
+            },
+        );
         Ok(initial_state)
     }
 
-    /// generates a solvable state with the specified dimensions
-    pub fn new_randomized(rows: usize, cols: usize) -> Result<State> {
-        let mut all_positions = (0..cols)
-            .cartesian_product(0..rows)
-            .map(Pos::from)
-            .collect_vec();
+    /// parses the text level format: one line per row, one character per tile,
+    /// as `Tile` displays them. the grid ends at the first blank line, and anything after it is ignored.
+    /// lines starting with `#` are comments, and are skipped wherever they are.
+    pub fn from_str_grid(s: &str) -> Result<State, GameError> {
+        Ok(State::parse_level(s)?.state)
+    }
 
-        //we want to find the first solution that is both valid (solvable)
-        //and non-trivial (not too easy).
+    /// like `from_str_grid`, but also keeps the level's name, if a comment before the grid gives one.
+    /// errors refer to lines by where they are in `s`, comments included.
+    pub fn parse_level(s: &str) -> Result<Level, GameError> {
+        let mut name = None;
+        //each row of the grid, along with its line number
+        let mut lines = Vec::new();
 
-        let mut rng = WyRand::new();
-        let initial_states = iter::repeat_with(|| {
-            let max_robots = cmp::max(rows, cols);
-            let num_robots = rng.generate_range(0..max_robots);
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim_end();
 
-            assert!(num_robots + 2 < all_positions.len());
+            if let Some(comment) = line.trim_start().strip_prefix('#') {
+                if lines.is_empty() && name.is_none() {
+                    name = comment
+                        .trim()
+                        .strip_prefix("name:")
+                        .map(|name| name.trim().to_string());
+                }
+                continue;
+            }
 
-            rng.shuffle(&mut all_positions);
-            let mut shuffled = all_positions.iter().copied();
+            match (line.is_empty(), lines.is_empty()) {
+                (true, true) => continue,
+                (true, false) => break,
+                (false, _) => lines.push((i + 1, line)),
+            }
+        }
 
-            let astro = shuffled.next().unwrap();
-            let goal = shuffled.next().unwrap();
-            let robots = shuffled.take(num_robots).collect();
+        let cols = lines
+            .first()
+            .map(|(_, line)| line.chars().count())
+            .ok_or(GameError::EmptyGrid)?;
 
-            State {
-                astro,
-                robots,
-                invariants: Invariants { goal, rows, cols },
+        let mut tiles = Vec::with_capacity(lines.len() * cols);
+        for &(line_number, line) in &lines {
+            let len = line.chars().count();
+            if len != cols {
+                //where the row should have ended, or where it ended too soon
+                return Err(GameError::ParseError {
+                    line: line_number,
+                    col: len.min(cols) + 1,
+                    reason: format!("expected {cols} tiles, found {len}"),
+                });
             }
-        });
 
-        let candidate_validate_attempts = 5000;
-        let is_non_trivial = |solution: &Vec<State>| solution.len() >= 5;
+            for (x, c) in line.chars().enumerate() {
+                let tile = Tile::try_from(c).map_err(|err| GameError::ParseError {
+                    line: line_number,
+                    col: x + 1,
+                    reason: err.to_string(),
+                })?;
+                tiles.push(tile);
+            }
+        }
 
-        initial_states
-            .take(candidate_validate_attempts)
-            .filter_map(|state| state.solve_from_here())
-            .find_map(|mut solution| {
-                if is_non_trivial(&solution) {
-                    let initial_state_of_solution = solution.swap_remove(0);
-                    Some(initial_state_of_solution)
-                } else {
-                    None
-                }
+        let state = State::from_grid(&Grid::new(cols, lines.len(), tiles))?;
+        Ok(Level { state, name })
+    }
+
+    /// like `from_str_grid`, but with each row given separately, which is handy for writing boards inline:
+    /// `State::from_lines(&["R..R.", ".....", "..X..", ".....", ".A..."])`
+    pub fn from_lines(lines: &[&str]) -> Result<State, GameError> {
+        //`from_str_grid` would stop at a blank row instead of rejecting it
+        if let Some(y) = lines.iter().position(|line| line.trim_end().is_empty()) {
+            return Err(GameError::ParseError {
+                line: y + 1,
+                col: 1,
+                reason: "empty row".to_string(),
+            });
+        }
+
+        State::from_str_grid(&lines.join("\n"))
+    }
+
+    /// parses the JSON level format: an object whose `grid` is an array of rows as `from_lines` takes them,
+    /// along with any of `name`, `author`, `difficulty` and `par`, which are returned as they are.
+    /// the par isn't checked against the board here, see `LevelMeta::verify_par`
+    pub fn from_level_json(s: &str) -> Result<(State, LevelMeta)> {
+        let invalid = |reason: &str| GameError::InvalidLevel(reason.to_string());
+
+        let level: serde_json::Value = serde_json::from_str(s)
+            .map_err(|err| GameError::InvalidLevel(format!("not valid JSON: {err}")))?;
+        let level = level
+            .as_object()
+            .ok_or_else(|| invalid("a level has to be a JSON object"))?;
+
+        let field = |key: &str| level.get(key).filter(|value| !value.is_null());
+        let string = |key: &str| -> Result<Option<String>, GameError> {
+            field(key)
+                .map(|value| {
+                    value.as_str().map(str::to_string).ok_or_else(|| {
+                        GameError::InvalidLevel(format!("`{key}` has to be a string"))
+                    })
+                })
+                .transpose()
+        };
+        let number = |key: &str| -> Result<Option<usize>, GameError> {
+            field(key)
+                .map(|value| {
+                    value
+                        .as_u64()
+                        .and_then(|n| usize::try_from(n).ok())
+                        .ok_or_else(|| {
+                            GameError::InvalidLevel(format!("`{key}` has to be a whole number"))
+                        })
+                })
+                .transpose()
+        };
+
+        let difficulty = number("difficulty")?
+            .map(|stars| {
+                u8::try_from(stars)
+                    .ok()
+                    .filter(|stars| (1..=5).contains(stars))
+                    .ok_or_else(|| invalid("`difficulty` has to be from 1 to 5 stars"))
+            })
+            .transpose()?;
+        let meta = LevelMeta {
+            name: string("name")?,
+            author: string("author")?,
+            difficulty,
+            par: number("par")?,
+        };
+
+        let rows: Vec<&str> = field("grid")
+            .and_then(serde_json::Value::as_array)
+            .ok_or_else(|| invalid("`grid` has to be an array of rows"))?
+            .iter()
+            .map(|row| {
+                row.as_str()
+                    .ok_or_else(|| invalid("each row of `grid` has to be a string"))
             })
-            .ok_or_else(|| eyre!("all generated positions failed validation"))
+            .try_collect()?;
+        let state = State::from_lines(&rows)?;
+
+        Ok((state, meta))
     }
 
-    pub fn pos_changes(states: &[State]) -> impl Iterator<Item = Result<PosChange>> + '_ {
-        states
-            .windows(2)
-            .map(|win| (&win[0], &win[1]))
-            .map(PosChange::try_from)
-            .map(|res| res.wrap_err("paths have different invariants"))
+    /// the same board turned a quarter clockwise, so that rows become columns
+    pub fn rotate_90(&self) -> State {
+        let (rows, cols) = self.dims();
+        self.transformed((cols, rows), |Pos { x, y }| Pos {
+            x: rows - 1 - y,
+            y: x,
+        })
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct PosChange(pub Pos, pub Pos);
+    /// the same board turned upside down
+    pub fn rotate_180(&self) -> State {
+        self.rotate_90().rotate_90()
+    }
 
-impl Display for PosChange {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let PosChange(s, t) = self;
-        write!(f, "{s} => {t}")
+    /// the same board turned a quarter counterclockwise
+    pub fn rotate_270(&self) -> State {
+        self.rotate_180().rotate_90()
     }
-}
 
-impl TryFrom<(&State, &State)> for PosChange {
-    type Error = Report;
+    /// the same board flipped left to right
+    pub fn mirror_horizontal(&self) -> State {
+        let (rows, cols) = self.dims();
+        self.transformed((rows, cols), |Pos { x, y }| Pos { x: cols - 1 - x, y })
+    }
 
-    fn try_from((s, t): (&State, &State)) -> Result<PosChange> {
-        ensure!(s.invariants == t.invariants, "state invariants differ");
-        ensure!(s.num_robots() == t.num_robots(), "number of robots differs");
+    /// the same board flipped top to bottom
+    pub fn mirror_vertical(&self) -> State {
+        let (rows, cols) = self.dims();
+        self.transformed((rows, cols), |Pos { x, y }| Pos { x, y: rows - 1 - y })
+    }
 
-        //returns the first difference instead of validating all positions
-        if s.astro != t.astro {
-            return Ok(PosChange(s.astro, t.astro));
-        }
+    /// a hash of the board that's the same for all of its rotations and reflections,
+    /// and for any order its robots are listed in, so that duplicate levels can be found.
+    /// stable across versions and platforms, so fingerprints can be stored and compared later.
+    pub fn fingerprint(&self) -> u64 {
+        let mirrored = self.mirror_horizontal();
+        let symmetries = [
+            self.clone(),
+            self.rotate_90(),
+            self.rotate_180(),
+            self.rotate_270(),
+            mirrored.rotate_90(),
+            mirrored.rotate_180(),
+            mirrored.rotate_270(),
+            mirrored,
+        ];
 
-        iter::zip(&s.robots, &t.robots)
-            .find_map(|(&s_pos, &t_pos)| {
-                if s_pos != t_pos {
-                    Some(PosChange(s_pos, t_pos))
+        symmetries
+            .iter()
+            .map(|state| fnv1a(state.canonical().to_debug_line().as_bytes()))
+            .min()
+            .expect("not empty")
+    }
+
+    /// the same board with its robots, goals and blockers in order of position,
+    /// the robot a win condition names being renumbered to match
+    fn canonical(&self) -> State {
+        let mut state = self.clone();
+        state.robots.sort();
+
+        let invariants = Rc::make_mut(&mut state.invariants);
+        invariants.goals.sort();
+        invariants.blockers.sort();
+        if let WinCondition::Occupant(GoalOccupant::Robot(n)) = invariants.win_condition {
+            let target = self.robots[n];
+            let n = state.robots.iter().position(|&robot| robot == target);
+            let n = n.expect("the same robots");
+            invariants.win_condition = WinCondition::Occupant(GoalOccupant::Robot(n));
+        }
+
+        state
+    }
+
+    /// moves everything on the board with `f`, onto a board of `dims` (rows, cols).
+    /// `f` has to map the board onto the new dimensions one to one, so that the result is still valid.
+    fn transformed(&self, dims: (usize, usize), f: impl Fn(Pos) -> Pos) -> State {
+        let (rows, cols) = dims;
+        let invariants = &self.invariants;
+
+        State::from_parts(
+            f(self.astro),
+            self.partner.map(&f),
+            self.robots.iter().copied().map(&f).collect(),
+            Invariants {
+                goals: invariants.goals.iter().copied().map(&f).collect(),
+                blockers: invariants.blockers.iter().copied().map(&f).collect(),
+                rows,
+                cols,
+                ..Invariants::clone(invariants)
+            },
+        )
+    }
+
+    pub fn to_grid(&self) -> Grid<Tile> {
+        let (rows, cols) = self.dims();
+        let tiles = (0..rows)
+            .cartesian_product(0..cols)
+            .map(|(y, x)| self.tile_at(Pos { x, y }));
+
+        Grid::new(cols, rows, tiles.collect())
+    }
+
+    /// the inverse of `from_str_grid`
+    pub fn to_str_grid(&self) -> String {
+        let grid = self.to_grid();
+        grid.rows().map(|y| grid.row_iter(y).join("")).join("\n")
+    }
+
+    /// a one-line, human-readable encoding of the state for logs and error messages,
+    /// e.g. `5x5|A=1,4|X=2,2|R=0,0;0,2;0,4;3,4`, where dimensions are rows by columns
+    /// and positions are `x,y`. blockers are listed as `|B=...` if there are any,
+    /// a second astro as `|P=x,y` if there is one, and `|W=all` is added when every piece needs to reach a goal.
+    pub fn to_debug_line(&self) -> String {
+        let positions = |positions: &[Pos]| {
+            positions
+                .iter()
+                .map(|Pos { x, y }| format!("{x},{y}"))
+                .join(";")
+        };
+
+        let State {
+            astro: Pos { x, y },
+            partner,
+            robots,
+            invariants,
+            ..
+        } = self;
+        let Invariants {
+            goals,
+            win_condition,
+            stop_rule,
+            collision_rule,
+            movement,
+            blockers,
+            rows,
+            cols,
+        } = &**invariants;
+
+        let mut line = format!(
+            "{rows}x{cols}|A={x},{y}|X={}|R={}",
+            positions(goals),
+            positions(robots)
+        );
+        if !blockers.is_empty() {
+            line += &format!("|B={}", positions(blockers));
+        }
+        if let Some(Pos { x, y }) = partner {
+            line += &format!("|P={x},{y}");
+        }
+        match stop_rule {
+            StopRule::Adjacent => (),
+            StopRule::OnGoal => line += "|S=goal",
+            StopRule::RobotsAvoidGoal => line += "|S=no-robots",
+        }
+        match collision_rule {
+            CollisionRule::Stop => (),
+            CollisionRule::Push => line += "|C=push",
+        }
+        match movement {
+            Movement::Orthogonal => (),
+            Movement::EightWay => line += "|M=8",
+        }
+        match win_condition {
+            WinCondition::AstroOnly => (),
+            WinCondition::AllPieces => line += "|W=all",
+            WinCondition::Occupant(GoalOccupant::AnyRobot) => line += "|W=robot",
+            WinCondition::Occupant(GoalOccupant::Robot(n)) => line += &format!("|W=robot{n}"),
+        }
+        line
+    }
+
+    /// parses the output of `to_debug_line`
+    pub fn from_debug_line(line: &str) -> Result<State> {
+        let parse_pos = |s: &str| -> Result<Pos> {
+            let (x, y) = s
+                .split_once(',')
+                .ok_or_else(|| eyre!("`{s}` is not a position"))?;
+            Ok(Pos {
+                x: x.parse().wrap_err_with(|| format!("bad x in `{s}`"))?,
+                y: y.parse().wrap_err_with(|| format!("bad y in `{s}`"))?,
+            })
+        };
+        let parse_positions = |s: &str| -> Result<Vec<Pos>> {
+            s.split(';')
+                .filter(|pos| !pos.is_empty())
+                .map(parse_pos)
+                .collect()
+        };
+
+        let mut fields = line.trim().split('|');
+        let dims = fields.next().unwrap_or_default();
+        let (rows, cols) = dims
+            .split_once('x')
+            .ok_or_else(|| eyre!("`{dims}` is not a pair of dimensions"))?;
+        let rows = rows.parse().wrap_err("bad number of rows")?;
+        let cols = cols.parse().wrap_err("bad number of columns")?;
+
+        let (mut astro, mut partner, mut goals, mut robots, mut blockers) =
+            (None, None, Vec::new(), Vec::new(), Vec::new());
+        let mut win_condition = WinCondition::AstroOnly;
+        let mut stop_rule = StopRule::Adjacent;
+        let mut collision_rule = CollisionRule::Stop;
+        let mut movement = Movement::Orthogonal;
+        for field in fields {
+            let (key, value) = field
+                .split_once('=')
+                .ok_or_else(|| eyre!("`{field}` is not a key=value pair"))?;
+            match key {
+                "A" => astro = Some(parse_pos(value)?),
+                "P" => partner = Some(parse_pos(value)?),
+                "X" => goals = parse_positions(value)?,
+                "R" => robots = parse_positions(value)?,
+                "B" => blockers = parse_positions(value)?,
+                "W" => {
+                    win_condition = match value {
+                        "astro" => WinCondition::AstroOnly,
+                        "all" => WinCondition::AllPieces,
+                        "robot" => WinCondition::Occupant(GoalOccupant::AnyRobot),
+                        _ => match value.strip_prefix("robot").map(str::parse) {
+                            Some(Ok(n)) => WinCondition::Occupant(GoalOccupant::Robot(n)),
+                            _ => return Err(eyre!("unknown win condition `{value}`")),
+                        },
+                    }
+                }
+                "S" => {
+                    stop_rule = match value {
+                        "adjacent" => StopRule::Adjacent,
+                        "goal" => StopRule::OnGoal,
+                        "no-robots" => StopRule::RobotsAvoidGoal,
+                        _ => return Err(eyre!("unknown stop rule `{value}`")),
+                    }
+                }
+                "C" => {
+                    collision_rule = match value {
+                        "stop" => CollisionRule::Stop,
+                        "push" => CollisionRule::Push,
+                        _ => return Err(eyre!("unknown collision rule `{value}`")),
+                    }
+                }
+                "M" => {
+                    movement = match value {
+                        "4" => Movement::Orthogonal,
+                        "8" => Movement::EightWay,
+                        _ => return Err(eyre!("unknown movement `{value}`")),
+                    }
+                }
+                _ => return Err(eyre!("unknown field `{key}`")),
+            }
+        }
+
+        let astro = astro.ok_or_else(|| eyre!("no player"))?;
+        let (&goal, extra_goals) = goals.split_first().ok_or_else(|| eyre!("no goal"))?;
+        let mut state = State::new(astro, goal, robots, rows, cols)?;
+
+        for &goal in extra_goals {
+            ensure!(
+                goal.x < cols && goal.y < rows,
+                "goal at {goal} is out of bounds"
+            );
+            ensure!(
+                state.tile_at(goal) == Tile::Empty,
+                "goal at {goal} overlaps another tile"
+            );
+            Rc::make_mut(&mut state.invariants).goals.push(goal);
+        }
+
+        for &blocker in &blockers {
+            ensure!(
+                blocker.x < cols && blocker.y < rows,
+                "blocker at {blocker} is out of bounds"
+            );
+            ensure!(
+                state.tile_at(blocker) == Tile::Empty,
+                "blocker at {blocker} overlaps another tile"
+            );
+            Rc::make_mut(&mut state.invariants).blockers.push(blocker);
+        }
+        if let Some(partner) = partner {
+            state = state.with_partner(partner)?;
+        }
+
+        state
+            .with_stop_rule(stop_rule)
+            .with_collision_rule(collision_rule)
+            .with_movement(movement)
+            .with_win_condition(win_condition)
+    }
+
+    /// generates a solvable state with the specified dimensions
+    pub fn new_randomized(rows: usize, cols: usize) -> Result<State> {
+        State::generate(rows, cols, &GenerationOptions::default()).map(|generated| generated.state)
+    }
+
+    /// like `new_randomized`, but the same seed always generates the same state
+    pub fn new_randomized_with_seed(rows: usize, cols: usize, seed: u64) -> Result<State> {
+        let options = GenerationOptions {
+            seed: Some(seed),
+            ..GenerationOptions::default()
+        };
+        State::generate(rows, cols, &options).map(|generated| generated.state)
+    }
+
+    /// like `new_randomized`, but the optimal solution takes a number of moves within `moves`
+    pub fn new_randomized_in_range(
+        rows: usize,
+        cols: usize,
+        moves: RangeInclusive<usize>,
+    ) -> Result<State> {
+        let options = GenerationOptions {
+            moves,
+            ..GenerationOptions::default()
+        };
+        State::generate(rows, cols, &options).map(|generated| generated.state)
+    }
+
+    /// randomly generates a solvable state whose optimal solution length is in `options.moves`.
+    ///
+    /// tries `options.attempts` candidates first, and doubles the budget each time
+    /// that isn't enough, up to `options.max_attempts` candidates in total.
+    pub fn generate(rows: usize, cols: usize, options: &GenerationOptions) -> Result<Generated> {
+        let mut rng = match options.seed {
+            Some(seed) => WyRand::new_seed(seed),
+            None => WyRand::new(),
+        };
+        State::generate_with_rng(rows, cols, options, &mut rng)
+    }
+
+    /// like `generate`, but draws from `rng` rather than a `WyRand` seeded from `options.seed`,
+    /// which is ignored. the same `rng` state always gives the same board.
+    pub fn generate_with_rng<const N: usize>(
+        rows: usize,
+        cols: usize,
+        options: &GenerationOptions,
+        rng: &mut impl Rng<N>,
+    ) -> Result<Generated> {
+        ensure!(
+            options.attempts > 0,
+            "at least one generation attempt is needed"
+        );
+        ensure!(
+            !options.moves.is_empty(),
+            "the minimum number of moves is larger than the maximum"
+        );
+
+        //there has to be room for the astro and the goal as well, and for a second of each
+        let others = if options.partner { 4 } else { 2 };
+        let capacity = (rows * cols).saturating_sub(others);
+        ensure!(
+            options.min_robots < capacity,
+            "a {rows}x{cols} board has no room for {} robots along with the astros and the goals",
+            options.min_robots
+        );
+        let density = robot_density(rows, cols);
+        let area = (rows * cols) as f64;
+        let [fewest, most] =
+            [density.start(), density.end()].map(|share| (share * area).ceil() as usize);
+        let most = most.clamp(options.min_robots, capacity - 1);
+        let robot_counts = fewest.clamp(options.min_robots, most)..=most;
+
+        let mut all_positions = (0..cols)
+            .cartesian_product(0..rows)
+            .map(Pos::from)
+            .collect_vec();
+
+        //we want to find the first solution that is both valid (solvable)
+        //and non-trivial (not too easy).
+
+        let mut initial_states = iter::repeat_with(|| {
+            let num_robots = rng.generate_range(robot_counts.clone());
+
+            assert!(num_robots + others < all_positions.len());
+
+            rng.shuffle(&mut all_positions);
+            let mut shuffled = all_positions.iter().copied();
+
+            let astro = shuffled.next().unwrap();
+            let goal = shuffled.next().unwrap();
+            //the goal scattered for the second astro, along with it
+            let partner = options
+                .partner
+                .then(|| (shuffled.next().unwrap(), shuffled.next().unwrap()));
+            let robots = match options.placement {
+                Placement::Uniform => shuffled.take(num_robots).collect(),
+                Placement::NearRoute => {
+                    //positions stay shuffled among the ones at the same distance, and a little
+                    //randomness in the distance still lets a robot land further out now and then
+                    let mut nearest = shuffled.collect_vec();
+                    nearest.sort_by_cached_key(|&pos| {
+                        distance_to_route(pos, astro, goal) + rng.generate_range(0..=1_usize)
+                    });
+                    nearest.truncate(num_robots);
+                    nearest
+                }
+            };
+
+            let state = State::from_parts(
+                astro,
+                partner.map(|(partner, _)| partner),
+                robots,
+                Invariants {
+                    goals: iter::once(goal)
+                        .chain(partner.map(|(_, goal)| goal))
+                        .collect(),
+                    win_condition: WinCondition::AstroOnly,
+                    stop_rule: options.stop_rule,
+                    collision_rule: options.collision_rule,
+                    movement: options.movement,
+                    blockers: Vec::new(),
+                    rows,
+                    cols,
+                },
+            );
+
+            match options.win_condition {
+                WinCondition::AstroOnly => Some(state),
+                //goals scattered at random almost never let every piece reach one
+                WinCondition::AllPieces => state.with_walked_goals(rng),
+                //candidates without the robot that has to reach the goal are skipped
+                occupant @ WinCondition::Occupant(_) => state.with_win_condition(occupant).ok(),
+            }
+        });
+
+        //the solution starts with the initial state
+        let has_wanted_length =
+            |solution: &Vec<State>| options.moves.contains(&(solution.len() - 1));
+
+        let mut attempts = 0;
+        let mut budget = options.attempts;
+        while attempts < options.max_attempts {
+            budget = budget.min(options.max_attempts - attempts);
+            log::debug!("trying {budget} {rows}x{cols} candidates, after {attempts} so far");
+
+            //the rng keeps going between rounds, so a seed still always gives the same state
+            let found = initial_states
+                .by_ref()
+                .take(budget)
+                .enumerate()
+                .find_map(|(i, state)| {
+                    let state = state?;
+                    //costs next to nothing next to searching, so it goes before anything else
+                    if state.has_trivial_dead_end() {
+                        return None;
+                    }
+                    //moving only the astro is cheap to search, so it's ruled out first
+                    if options.require_robot_moves && state.solve_astro_only().is_some() {
+                        return None;
+                    }
+                    let mut solution = match options.robot_moves {
+                        Some(moves) => state.solve_with_move_limits(&solver::robot_move_limits(
+                            state.num_robots(),
+                            moves,
+                        )),
+                        None => state.solve_from_here(),
+                    }?;
+                    has_wanted_length(&solution).then(|| (i, solution.swap_remove(0)))
+                });
+
+            if let Some((i, state)) = found {
+                log::info!(
+                    "accepted a {rows}x{cols} board with {} robots after {} candidates",
+                    state.num_robots(),
+                    attempts + i + 1
+                );
+                return Ok(Generated {
+                    state,
+                    attempts: attempts + i + 1,
+                });
+            }
+
+            attempts += budget;
+            budget = budget.saturating_mul(2);
+        }
+
+        let (min, max) = (options.moves.start(), options.moves.end());
+        let moves = if *max == usize::MAX {
+            format!("at least {min}")
+        } else {
+            format!("{min} to {max}")
+        };
+        Err(eyre!(
+            "no solvable {rows}x{cols} board with {moves} moves was found after {attempts} attempts"
+        ))
+    }
+
+    /// requires every piece to reach a goal, placing the goals where the pieces end up
+    /// after moving at random, so that they can always be reached.
+    /// `None` if a piece would start on a goal.
+    fn with_walked_goals<const N: usize>(mut self, rng: &mut impl Rng<N>) -> Option<State> {
+        let directions = self.movement().directions();
+        let (rows, cols) = self.dims();
+
+        let mut walked = self.clone();
+        let selections = self.selections().collect_vec();
+        for _ in 0..rows * cols * 2 {
+            let selection = selections[rng.generate_range(0..selections.len())];
+            let direction = directions[rng.generate_range(0..directions.len())];
+
+            let attempt = walked.move_toward(walked.pos_of(selection), direction);
+            if let MovementAttempt::Success(new_pos) = attempt {
+                let moved = walked.try_move(selection, new_pos);
+                debug_assert_eq!(moved, Ok(()));
+            }
+        }
+
+        let goals = walked
+            .astros()
+            .chain(walked.robots.iter().copied())
+            .collect_vec();
+        let starts_on_goal = self
+            .astros()
+            .chain(self.robots.iter().copied())
+            .any(|piece| goals.contains(&piece));
+        if starts_on_goal {
+            return None;
+        }
+
+        let invariants = Rc::make_mut(&mut self.invariants);
+        invariants.goals = goals;
+        invariants.win_condition = WinCondition::AllPieces;
+        Some(self)
+    }
+
+    pub fn pos_changes(states: &[State]) -> impl Iterator<Item = Result<PosChange>> + '_ {
+        states
+            .windows(2)
+            .map(|win| (&win[0], &win[1]))
+            .map(PosChange::try_from)
+            .map(|res| res.wrap_err("paths have different invariants"))
+    }
+
+    /// the states that `changes` lead through when made one after the other, starting with this one.
+    /// fails if any of them isn't a legal move.
+    pub fn replay(&self, changes: &[PosChange]) -> Result<Vec<State>> {
+        let mut states = vec![self.clone()];
+
+        for (i, change) in changes.iter().enumerate() {
+            let move_number = i + 1;
+            let state = states.last().expect("states are never empty");
+            let PosChange(from, to) = *change;
+
+            let selection = state
+                .selection_at(from)
+                .ok_or_else(|| eyre!("move {move_number}: no piece to move at {from}"))?;
+            let direction = change
+                .direction()
+                .ok_or_else(|| eyre!("move {move_number}: {change} is not a straight line"))?;
+            ensure!(
+                matches!(state.move_toward(from, direction), MovementAttempt::Success(pos) if pos == to),
+                "move {move_number}: the piece at {from} doesn't stop at {to}"
+            );
+
+            let mut next = state.clone();
+            next.try_move(selection, to)?;
+            states.push(next);
+        }
+
+        Ok(states)
+    }
+
+    /// `states` as move notation: one token per move, separated by spaces, like `A↑ R1← A→`.
+    /// each token is the piece that moved, `A` for the astro, `a` for the second astro or `R`
+    /// and the robot's number counting from 1, followed by an arrow for the direction it slid in.
+    pub fn to_notation(states: &[State]) -> Result<String> {
+        let tokens: Vec<String> = iter::zip(states, State::pos_changes(states))
+            .map(|(state, change)| {
+                let change = change?;
+                let piece = match state.selection_at(change.0) {
+                    Some(Selection::Astro) => "A".to_string(),
+                    Some(Selection::Partner) => "a".to_string(),
+                    Some(Selection::Robot(n)) => format!("R{}", n + 1),
+                    None => return Err(eyre!("no piece to move at {}", change.0)),
+                };
+                let direction = change
+                    .direction()
+                    .ok_or_else(|| eyre!("{change} is not a straight line"))?;
+                Ok(format!("{piece}{}", direction.arrow()))
+            })
+            .try_collect()?;
+
+        Ok(tokens.join(" "))
+    }
+
+    /// the states that the moves in `notation`, as written by `to_notation`, lead through
+    /// when made one after the other, starting with this one. fails if any of them isn't a legal move.
+    pub fn replay_notation(&self, notation: &str) -> Result<Vec<State>> {
+        let mut states = vec![self.clone()];
+
+        for (i, token) in notation.split_whitespace().enumerate() {
+            let move_number = i + 1;
+            let state = states.last().expect("states are never empty");
+
+            let mut chars = token.chars();
+            let direction = chars
+                .next_back()
+                .and_then(Direction::from_arrow)
+                .ok_or_else(|| eyre!("move {move_number}: `{token}` doesn't end with an arrow"))?;
+            let selection = match chars.as_str() {
+                "A" => Selection::Astro,
+                "a" if state.partner.is_some() => Selection::Partner,
+                piece => piece
+                    .strip_prefix('R')
+                    .and_then(|n| n.parse::<usize>().ok())
+                    .filter(|n| (1..=state.num_robots()).contains(n))
+                    .map(|n| Selection::Robot(n - 1))
+                    .ok_or_else(|| eyre!("move {move_number}: no piece called `{piece}`"))?,
+            };
+
+            let MovementAttempt::Success(to) =
+                state.move_toward(state.pos_of(selection), direction)
+            else {
+                return Err(eyre!("move {move_number}: `{token}` doesn't stop anywhere"));
+            };
+
+            let mut next = state.clone();
+            next.try_move(selection, to)?;
+            states.push(next);
+        }
+
+        Ok(states)
+    }
+
+    /// every piece that moved between `s` and `t`.
+    ///
+    /// converting to a single `PosChange` only reports the piece that was moved,
+    /// and not a robot it pushed under `CollisionRule::Push`.
+    pub fn all_pos_changes(s: &State, t: &State) -> Result<Vec<PosChange>> {
+        ensure!(s.invariants == t.invariants, "state invariants differ");
+        ensure!(s.num_robots() == t.num_robots(), "number of robots differs");
+        ensure!(
+            s.partner.is_some() == t.partner.is_some(),
+            "number of players differs"
+        );
+
+        let astros = iter::zip(s.astros(), t.astros());
+        let robots = iter::zip(s.robots.iter().copied(), t.robots.iter().copied());
+        let changes = astros
+            .chain(robots)
+            .filter(|(s_pos, t_pos)| s_pos != t_pos)
+            .map(|(s_pos, t_pos)| PosChange(s_pos, t_pos))
+            .collect_vec();
+
+        ensure!(!changes.is_empty(), "start and end states are equal");
+        Ok(changes)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PosChange(pub Pos, pub Pos);
+
+impl PosChange {
+    /// the direction a piece slid in to make this change, if it moved in a straight line
+    pub fn direction(&self) -> Option<Direction> {
+        let PosChange(s, t) = self;
+
+        //a diagonal move goes as far along one axis as the other
+        let diagonal = s.x.abs_diff(t.x) == s.y.abs_diff(t.y);
+        match (s.x.cmp(&t.x), s.y.cmp(&t.y)) {
+            (Ordering::Equal, Ordering::Greater) => Some(Direction::Up),
+            (Ordering::Equal, Ordering::Less) => Some(Direction::Down),
+            (Ordering::Greater, Ordering::Equal) => Some(Direction::Left),
+            (Ordering::Less, Ordering::Equal) => Some(Direction::Right),
+            (Ordering::Greater, Ordering::Greater) if diagonal => Some(Direction::UpLeft),
+            (Ordering::Less, Ordering::Greater) if diagonal => Some(Direction::UpRight),
+            (Ordering::Greater, Ordering::Less) if diagonal => Some(Direction::DownLeft),
+            (Ordering::Less, Ordering::Less) if diagonal => Some(Direction::DownRight),
+            _ => None,
+        }
+    }
+}
+
+/// the 64-bit FNV-1a hash of `bytes`, which unlike `std`'s hashers never changes between releases
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// the share of a board's tiles that a candidate's robots take up. small boards need a larger share,
+/// or the pieces have little to stop against and most boards are as easy as they're allowed to be.
+/// on large boards, every extra robot multiplies the positions the solver has to explore.
+fn robot_density(rows: usize, cols: usize) -> RangeInclusive<f64> {
+    if rows * cols <= 25 {
+        0.12..=0.2
+    } else {
+        0.02..=0.1
+    }
+}
+
+/// how many steps `pos` is from the nearest row or column that `astro` or `goal` is in
+fn distance_to_route(pos: Pos, astro: Pos, goal: Pos) -> usize {
+    [astro, goal]
+        .into_iter()
+        .flat_map(|end| [pos.x.abs_diff(end.x), pos.y.abs_diff(end.y)])
+        .min()
+        .expect("not empty")
+}
+
+/// parses a change the way it's displayed, as `(x, y) => (x, y)`
+impl FromStr for PosChange {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (from, to) = s
+            .split_once("=>")
+            .ok_or_else(|| eyre!("`{s}` is not a move"))?;
+        Ok(PosChange(from.parse()?, to.parse()?))
+    }
+}
+
+/// the board as `to_str_grid` draws it, below a line with its dimensions (rows by columns)
+impl Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (rows, cols) = self.dims();
+        writeln!(f, "{rows}x{cols}")?;
+        write!(f, "{}", self.to_str_grid())
+    }
+}
+
+impl Display for PosChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let PosChange(s, t) = self;
+        write!(f, "{s} => {t}")
+    }
+}
+
+impl PosChange {
+    /// the change of the piece that moved between `s` and `t`, when it may have pushed robots along.
+    /// the pushed robots are ahead of it, so it's the one that every other moved piece is in the path of.
+    fn pushing(s: &State, t: &State) -> Result<PosChange> {
+        let changes = State::all_pos_changes(s, t)?;
+        let is_mover = |change: &PosChange| {
+            change.direction().is_some_and(|direction| {
+                let path = s.positions_in_path(change.0, direction).collect_vec();
+                changes
+                    .iter()
+                    .all(|other| other == change || path.contains(&other.0))
+            })
+        };
+
+        match changes.iter().find(|change| is_mover(change)) {
+            Some(change) => Ok(change.clone()),
+            None if changes.is_empty() => Err(eyre!("start and end states are equal")),
+            None => Err(eyre!("several pieces moved, but not as a single push")),
+        }
+    }
+}
+
+impl TryFrom<(&State, &State)> for PosChange {
+    type Error = Report;
+
+    fn try_from((s, t): (&State, &State)) -> Result<PosChange> {
+        ensure!(s.invariants == t.invariants, "state invariants differ");
+        ensure!(s.num_robots() == t.num_robots(), "number of robots differs");
+        ensure!(
+            s.partner.is_some() == t.partner.is_some(),
+            "number of players differs"
+        );
+
+        if s.collision_rule() == CollisionRule::Push {
+            return PosChange::pushing(s, t);
+        }
+
+        //returns the first difference instead of validating all positions
+        let astros = iter::zip(s.astros(), t.astros());
+        let robots = iter::zip(s.robots.iter().copied(), t.robots.iter().copied());
+        astros
+            .chain(robots)
+            .find_map(|(s_pos, t_pos)| {
+                if s_pos != t_pos {
+                    Some(PosChange(s_pos, t_pos))
                 } else {
                     None
                 }
@@ -322,3 +1939,628 @@ impl TryFrom<(&State, &State)> for PosChange {
             .ok_or_else(|| eyre!("start and end states are equal"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opposite_directions_pair_up() {
+        assert_eq!(Direction::Up.opposite(), Direction::Down);
+        assert_eq!(Direction::Left.opposite(), Direction::Right);
+        assert_eq!(Direction::UpLeft.opposite(), Direction::DownRight);
+        assert_eq!(Direction::UpRight.opposite(), Direction::DownLeft);
+
+        for &direction in Movement::EightWay.directions() {
+            assert_ne!(direction.opposite(), direction);
+            assert_eq!(direction.opposite().opposite(), direction);
+        }
+    }
+
+    #[test]
+    fn solver_expands_moves_in_the_order_of_all_directions() {
+        assert_eq!(Movement::Orthogonal.directions(), Direction::all());
+
+        //a robot stops the astro one tile away in each direction
+        let state = state("X.R..\n.....\nR.A.R\n.....\n..R..");
+        let astro = Pos { x: 2, y: 2 };
+        let reached = state
+            .reachable_tiles()
+            .into_iter()
+            .filter(|&(selection, _)| selection == Selection::Astro)
+            .map(|(_, pos)| pos)
+            .collect_vec();
+        let expected =
+            Direction::all().map(|direction| astro.step(direction, state.dims()).unwrap());
+        assert_eq!(reached, expected);
+    }
+
+    //3 rows of 4 columns
+    const DIMS: (usize, usize) = (3, 4);
+
+    #[test]
+    fn step_moves_one_tile() {
+        let pos = Pos { x: 1, y: 1 };
+        assert_eq!(pos.step(Direction::Up, DIMS), Some(Pos { x: 1, y: 0 }));
+        assert_eq!(pos.step(Direction::Right, DIMS), Some(Pos { x: 2, y: 1 }));
+        assert_eq!(
+            pos.step(Direction::DownLeft, DIMS),
+            Some(Pos { x: 0, y: 2 })
+        );
+    }
+
+    #[test]
+    fn step_stops_at_the_edges() {
+        let top_left = Pos { x: 0, y: 0 };
+        assert_eq!(top_left.step(Direction::Up, DIMS), None);
+        assert_eq!(top_left.step(Direction::Left, DIMS), None);
+        assert_eq!(top_left.step(Direction::UpRight, DIMS), None);
+
+        let bottom_right = Pos { x: 3, y: 2 };
+        assert_eq!(bottom_right.step(Direction::Down, DIMS), None);
+        assert_eq!(bottom_right.step(Direction::Right, DIMS), None);
+    }
+
+    #[test]
+    fn checked_offset_stays_within_rows_and_cols() {
+        let pos = Pos { x: 0, y: 0 };
+        assert_eq!(pos.checked_offset(3, 2, DIMS), Some(Pos { x: 3, y: 2 }));
+        //the grid is wider than it is tall, so the same offset only fits across
+        assert_eq!(pos.checked_offset(3, 0, DIMS), Some(Pos { x: 3, y: 0 }));
+        assert_eq!(pos.checked_offset(0, 3, DIMS), None);
+        assert_eq!(pos.checked_offset(4, 0, DIMS), None);
+        assert_eq!(pos.checked_offset(-1, 0, DIMS), None);
+    }
+
+    fn path(start: Pos, direction: Direction) -> Vec<Pos> {
+        //3 rows of 4 columns, like `DIMS`
+        state("A...\n....\n...X")
+            .positions_in_path(start, direction)
+            .collect()
+    }
+
+    #[test]
+    fn paths_run_nearest_first_up_to_the_edge() {
+        let start = Pos { x: 1, y: 1 };
+        let pos = |x, y| Pos { x, y };
+
+        assert_eq!(path(start, Direction::Up), [pos(1, 0)]);
+        assert_eq!(path(start, Direction::Down), [pos(1, 2)]);
+        assert_eq!(path(start, Direction::Left), [pos(0, 1)]);
+        assert_eq!(path(start, Direction::Right), [pos(2, 1), pos(3, 1)]);
+        assert_eq!(path(start, Direction::UpLeft), [pos(0, 0)]);
+        assert_eq!(path(start, Direction::DownRight), [pos(2, 2)]);
+        assert_eq!(
+            path(Pos { x: 0, y: 2 }, Direction::UpRight),
+            [pos(1, 1), pos(2, 0)]
+        );
+        assert_eq!(
+            path(Pos { x: 3, y: 0 }, Direction::DownLeft),
+            [pos(2, 1), pos(1, 2)]
+        );
+    }
+
+    #[test]
+    fn paths_from_the_edge_toward_it_are_empty() {
+        let corner = Pos { x: 3, y: 2 };
+
+        assert_eq!(path(corner, Direction::Down), []);
+        assert_eq!(path(corner, Direction::Right), []);
+        assert_eq!(path(corner, Direction::UpRight), []);
+        assert_eq!(path(corner, Direction::DownLeft), []);
+        //but cross the whole grid the other way
+        assert_eq!(
+            path(corner, Direction::Left),
+            [Pos { x: 2, y: 2 }, Pos { x: 1, y: 2 }, Pos { x: 0, y: 2 }]
+        );
+        assert_eq!(
+            path(corner, Direction::Up),
+            [Pos { x: 3, y: 1 }, Pos { x: 3, y: 0 }]
+        );
+    }
+
+    #[test]
+    fn paths_know_how_long_they_are() {
+        let state = state("A...\n....\n...X");
+        let starts = [Pos { x: 0, y: 0 }, Pos { x: 1, y: 1 }, Pos { x: 3, y: 2 }];
+
+        for start in starts {
+            for &direction in Movement::EightWay.directions() {
+                let path = state.positions_in_path(start, direction);
+                let remaining = path.clone().count();
+                assert_eq!(
+                    path.size_hint(),
+                    (remaining, Some(remaining)),
+                    "{start:?} {direction:?}"
+                );
+            }
+        }
+    }
+
+    fn state(grid: &str) -> State {
+        State::from_str_grid(grid).expect("a valid grid")
+    }
+
+    fn new_state(
+        astro: [usize; 2],
+        goal: [usize; 2],
+        robots: &[[usize; 2]],
+    ) -> Result<State, GameError> {
+        let robots = robots.iter().copied().map(Pos::from).collect();
+        State::new(astro.into(), goal.into(), robots, 3, 4)
+    }
+
+    #[test]
+    fn new_accepts_pieces_apart_on_the_grid() {
+        assert!(new_state([0, 0], [3, 2], &[[1, 0], [3, 0]]).is_ok());
+    }
+
+    #[test]
+    fn new_rejects_an_astro_off_the_grid() {
+        assert_eq!(
+            new_state([4, 0], [0, 0], &[]).unwrap_err(),
+            GameError::OutOfBounds {
+                piece: "player",
+                pos: Pos { x: 4, y: 0 }
+            }
+        );
+    }
+
+    #[test]
+    fn new_rejects_a_goal_off_the_grid() {
+        assert_eq!(
+            new_state([0, 0], [0, 3], &[]).unwrap_err(),
+            GameError::OutOfBounds {
+                piece: "goal",
+                pos: Pos { x: 0, y: 3 }
+            }
+        );
+    }
+
+    #[test]
+    fn new_rejects_a_robot_off_the_grid() {
+        assert_eq!(
+            new_state([0, 0], [1, 1], &[[2, 5]]).unwrap_err(),
+            GameError::OutOfBounds {
+                piece: "robot",
+                pos: Pos { x: 2, y: 5 }
+            }
+        );
+    }
+
+    #[test]
+    fn new_rejects_an_astro_on_the_goal() {
+        assert_eq!(
+            new_state([1, 1], [1, 1], &[]).unwrap_err(),
+            GameError::Overlap {
+                first: "player",
+                second: "goal",
+                pos: Pos { x: 1, y: 1 }
+            }
+        );
+    }
+
+    #[test]
+    fn new_rejects_a_robot_on_the_astro() {
+        assert_eq!(
+            new_state([0, 0], [1, 1], &[[0, 0]]).unwrap_err(),
+            GameError::Overlap {
+                first: "robot",
+                second: "player",
+                pos: Pos { x: 0, y: 0 }
+            }
+        );
+    }
+
+    #[test]
+    fn new_rejects_a_robot_on_the_goal() {
+        assert_eq!(
+            new_state([0, 0], [1, 1], &[[1, 1]]).unwrap_err(),
+            GameError::Overlap {
+                first: "robot",
+                second: "goal",
+                pos: Pos { x: 1, y: 1 }
+            }
+        );
+    }
+
+    #[test]
+    fn new_rejects_overlapping_robots() {
+        assert_eq!(
+            new_state([0, 0], [1, 1], &[[2, 0], [2, 0]]).unwrap_err(),
+            GameError::Overlap {
+                first: "robot",
+                second: "robot",
+                pos: Pos { x: 2, y: 0 }
+            }
+        );
+    }
+
+    #[test]
+    fn selections_are_the_astro_and_then_each_robot() {
+        let state = state("A.R\n.R.\nR.X");
+
+        assert_eq!(state.selections().count(), state.num_robots() + 1);
+        assert_eq!(state.num_selections(), state.num_robots() + 1);
+        assert_eq!(
+            state.selections().collect_vec(),
+            [
+                Selection::Astro,
+                Selection::Robot(0),
+                Selection::Robot(1),
+                Selection::Robot(2)
+            ]
+        );
+    }
+
+    #[test]
+    fn selections_include_the_second_astro() {
+        let state = state("A.R\n...\naXX");
+
+        assert_eq!(state.selections().count(), state.num_robots() + 2);
+        assert_eq!(state.num_selections(), state.selections().count());
+        assert_eq!(
+            state.selections().collect_vec(),
+            [Selection::Astro, Selection::Partner, Selection::Robot(0)]
+        );
+    }
+
+    #[test]
+    fn goals_include_every_goal_on_the_board() {
+        let state = state("X..R\n....\nA..X");
+
+        assert_eq!(state.num_goals(), 2);
+        assert_eq!(
+            state.goal_positions(),
+            [Pos { x: 0, y: 0 }, Pos { x: 3, y: 2 }]
+        );
+        for &goal in state.goal_positions() {
+            assert_eq!(state.tile_at(goal), Tile::Goal);
+        }
+    }
+
+    #[test]
+    fn either_goal_wins() {
+        let mut state = state("X..R\n....\nA..X");
+        assert!(!state.is_at_goal());
+
+        state.astro = Pos { x: 0, y: 0 };
+        assert!(state.is_at_goal());
+        state.astro = Pos { x: 3, y: 2 };
+        assert!(state.is_at_goal());
+    }
+
+    #[test]
+    fn manhattan_to_goal_ignores_what_is_in_the_way() {
+        assert_eq!(state("A.R\n.R.\nR.X").manhattan_to_goal(), 4);
+    }
+
+    #[test]
+    fn manhattan_to_goal_takes_the_nearest_goal() {
+        assert_eq!(state("X...\n....\n..AX").manhattan_to_goal(), 1);
+    }
+
+    #[test]
+    fn manhattan_to_goal_adds_up_both_astros() {
+        assert_eq!(state("A..X\n....\na..X").manhattan_to_goal(), 6);
+    }
+
+    #[test]
+    fn from_lines_reads_the_same_board_as_the_text_format() {
+        let lines = State::from_lines(&["R..R.", ".....", "..X..", ".....", ".A..."]);
+        assert_eq!(
+            lines,
+            State::from_str_grid("R..R.\n.....\n..X..\n.....\n.A...")
+        );
+        assert!(lines.is_ok());
+    }
+
+    #[test]
+    fn from_lines_points_at_an_unknown_tile() {
+        assert_eq!(
+            State::from_lines(&["A..", ".?.", "..X"]).unwrap_err(),
+            GameError::ParseError {
+                line: 2,
+                col: 2,
+                reason: "unknown tile `?`".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn from_lines_points_at_where_a_short_row_ends() {
+        assert_eq!(
+            State::from_lines(&["A..", "..", "..X"]).unwrap_err(),
+            GameError::ParseError {
+                line: 2,
+                col: 3,
+                reason: "expected 3 tiles, found 2".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn from_lines_rejects_an_empty_row() {
+        assert_eq!(
+            State::from_lines(&["A..", "", "..X"]).unwrap_err(),
+            GameError::ParseError {
+                line: 2,
+                col: 1,
+                reason: "empty row".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn move_toward_fails_against_the_edge() {
+        let state = state("A.R\n...\n..X");
+        let astro = Pos { x: 0, y: 0 };
+
+        assert_eq!(
+            state.move_toward(astro, Direction::Up),
+            MovementAttempt::Failure
+        );
+        assert_eq!(
+            state.move_toward(astro, Direction::Left),
+            MovementAttempt::Failure
+        );
+    }
+
+    #[test]
+    fn move_toward_stops_before_a_robot_one_tile_away() {
+        let state = state("A.R\n...\n..X");
+
+        assert_eq!(
+            state.move_toward(Pos { x: 0, y: 0 }, Direction::Right),
+            MovementAttempt::Success(Pos { x: 1, y: 0 })
+        );
+    }
+
+    #[test]
+    fn move_toward_fails_when_nothing_stops_the_slide() {
+        //sliding to the far edge would take the piece off the grid
+        let state = state("A.R\n...\n..X");
+
+        assert_eq!(
+            state.move_toward(Pos { x: 0, y: 0 }, Direction::Down),
+            MovementAttempt::Failure
+        );
+    }
+
+    //3 rows of 5 columns, solved in a few moves
+    const WIDE: &str = ".R..R\n...X.\nA...R";
+
+    #[test]
+    fn rotation_swaps_rows_and_columns() {
+        let state = state(WIDE);
+        let rotated = state.rotate_90();
+
+        assert_eq!(state.dims(), (3, 5));
+        assert_eq!(rotated.dims(), (5, 3));
+        //clockwise, the bottom left corner comes round to the top left
+        assert_eq!(state.astro, Pos { x: 0, y: 2 });
+        assert_eq!(rotated.astro, Pos { x: 0, y: 0 });
+        assert_eq!(rotated.rotate_90().dims(), (3, 5));
+    }
+
+    #[test]
+    fn four_quarter_turns_give_back_the_same_board() {
+        let state = state(WIDE);
+
+        assert_eq!(state.rotate_90().rotate_90().rotate_90().rotate_90(), state);
+        assert_eq!(state.rotate_270().rotate_90(), state);
+        assert_eq!(state.mirror_horizontal().mirror_horizontal(), state);
+        assert_eq!(state.mirror_vertical().mirror_vertical(), state);
+        assert_eq!(
+            state.mirror_horizontal().mirror_vertical(),
+            state.rotate_180()
+        );
+    }
+
+    #[test]
+    fn rotations_and_reflections_keep_the_solution_length() {
+        let state = state(WIDE);
+        let moves = state.solve_from_here().expect("solvable").len();
+
+        let transformed = [
+            state.rotate_90(),
+            state.rotate_180(),
+            state.rotate_270(),
+            state.mirror_horizontal(),
+            state.mirror_vertical(),
+        ];
+        for other in transformed {
+            assert_eq!(other.solve_from_here().map(|path| path.len()), Some(moves));
+        }
+    }
+
+    #[test]
+    fn fingerprints_are_the_same_for_every_symmetry() {
+        let state = state(WIDE);
+        let fingerprint = state.fingerprint();
+
+        let mirrored = state.mirror_horizontal();
+        let symmetries = [
+            state.rotate_90(),
+            state.rotate_180(),
+            state.rotate_270(),
+            mirrored.rotate_90(),
+            mirrored.rotate_180(),
+            mirrored.rotate_270(),
+            mirrored,
+            state.mirror_vertical(),
+        ];
+        for other in symmetries {
+            assert_eq!(
+                other.fingerprint(),
+                fingerprint,
+                "{}",
+                other.to_debug_line()
+            );
+        }
+    }
+
+    #[test]
+    fn fingerprints_ignore_the_order_of_robots() {
+        let state = state(WIDE);
+        let mut reordered = state.clone();
+        reordered.robots.reverse();
+
+        assert_ne!(reordered, state);
+        assert_eq!(reordered.fingerprint(), state.fingerprint());
+    }
+
+    #[test]
+    fn fingerprints_tell_boards_apart() {
+        //`WIDE` with its astro slid along to the robot at the end of the bottom row
+        let other = state(".R..R\n...X.\n...AR");
+
+        assert_ne!(other.fingerprint(), state(WIDE).fingerprint());
+    }
+
+    #[test]
+    fn fingerprints_stay_the_same_across_versions() {
+        //stored fingerprints are compared against new ones, so this should never change
+        assert_eq!(
+            format!("{:016x}", state(WIDE).fingerprint()),
+            "23c3e2271ad0a7ac"
+        );
+    }
+
+    #[test]
+    fn move_toward_slides_diagonally_on_eight_way_boards() {
+        let state = state("A...\n....\n..X.\n...R").with_movement(Movement::EightWay);
+
+        assert_eq!(
+            state.move_toward(Pos { x: 0, y: 0 }, Direction::DownRight),
+            MovementAttempt::Success(Pos { x: 2, y: 2 })
+        );
+    }
+
+    #[test]
+    fn move_toward_fails_sliding_diagonally_off_a_corner() {
+        let state = state("A...\n.R..\n....\n..X.").with_movement(Movement::EightWay);
+
+        //through the opposite corner and off the grid
+        assert_eq!(
+            state.move_toward(Pos { x: 1, y: 1 }, Direction::DownRight),
+            MovementAttempt::Failure
+        );
+        //already in the corner
+        assert_eq!(
+            state.move_toward(Pos { x: 0, y: 0 }, Direction::UpLeft),
+            MovementAttempt::Failure
+        );
+    }
+
+    /// always draws the largest value, so every range gives its top and every shuffle
+    /// rotates the positions one place to the right
+    #[derive(Clone)]
+    struct MaxRng;
+
+    impl Rng<8> for MaxRng {
+        fn rand(&mut self) -> [u8; 8] {
+            [u8::MAX; 8]
+        }
+    }
+
+    #[test]
+    fn generation_places_pieces_as_the_rng_says() {
+        let options = GenerationOptions {
+            moves: 1..=usize::MAX,
+            ..GenerationOptions::default()
+        };
+        let generated = State::generate_with_rng(3, 3, &options, &mut MaxRng).expect("a board");
+
+        //the positions start out column by column, and each candidate rotates them once more.
+        //the first two candidates aren't accepted, so the third takes the pieces from three places on
+        assert_eq!(generated.attempts, 3);
+        let state = generated.state;
+        assert_eq!(state.astro, Pos { x: 2, y: 0 });
+        assert_eq!(state.goal_positions(), [Pos { x: 2, y: 1 }]);
+        //as many robots as a 3x3 board takes
+        assert_eq!(state.robots(), [Pos { x: 2, y: 2 }, Pos { x: 0, y: 0 }]);
+    }
+
+    fn with_min_robots(min_robots: usize) -> GenerationOptions {
+        GenerationOptions {
+            seed: Some(0),
+            min_robots,
+            moves: 1..=usize::MAX,
+            ..GenerationOptions::default()
+        }
+    }
+
+    #[test]
+    fn generation_fills_all_but_one_free_tile_with_robots() {
+        //the astro and the goal take two of the nine tiles
+        let state = State::generate(3, 3, &with_min_robots(6))
+            .expect("a board")
+            .state;
+        assert_eq!(state.num_robots(), 6);
+    }
+
+    #[test]
+    fn generation_rejects_more_robots_than_fit() {
+        let err = State::generate(3, 3, &with_min_robots(7)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a 3x3 board has no room for 7 robots along with the astros and the goals"
+        );
+    }
+
+    #[test]
+    fn generation_keeps_room_for_a_partner() {
+        let options = GenerationOptions {
+            partner: true,
+            ..with_min_robots(5)
+        };
+        let err = State::generate(3, 3, &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a 3x3 board has no room for 5 robots along with the astros and the goals"
+        );
+    }
+
+    #[test]
+    fn randomized_boards_take_a_number_of_moves_in_range() {
+        for _ in 0..5 {
+            let state = State::new_randomized_in_range(4, 4, 3..=4).expect("a board");
+            let moves = state.solve_from_here().expect("a solution").len() - 1;
+            assert!((3..=4).contains(&moves), "{moves} moves");
+        }
+    }
+
+    #[test]
+    fn generation_gives_up_on_a_range_it_cannot_find() {
+        let options = GenerationOptions {
+            seed: Some(0),
+            attempts: 10,
+            max_attempts: 30,
+            moves: 30..=40,
+            ..GenerationOptions::default()
+        };
+        let err = State::generate(3, 3, &options).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "no solvable 3x3 board with 30 to 40 moves was found after 30 attempts"
+        );
+    }
+
+    #[test]
+    fn generated_boards_need_robot_moves_under_push() {
+        for seed in 0..5 {
+            let options = GenerationOptions {
+                seed: Some(seed),
+                collision_rule: CollisionRule::Push,
+                require_robot_moves: true,
+                moves: 2..=usize::MAX,
+                ..GenerationOptions::default()
+            };
+            let state = State::generate(5, 5, &options).expect("a board").state;
+
+            assert_eq!(state.solve_astro_only(), None, "seed {seed}");
+            assert!(state.solve_from_here().is_some(), "seed {seed}");
+        }
+    }
+}
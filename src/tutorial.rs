@@ -0,0 +1,61 @@
+use crate::error::GameError;
+use crate::state::{State, Tile};
+use simple_grid::Grid;
+
+const SIZE: usize = 5;
+
+/// hand-made boards that introduce the rules one at a time, easiest first.
+static BOARDS: [[[Tile; SIZE]; SIZE]; 5] = {
+    use Tile::*;
+
+    [
+        //pieces stop right before whatever is in their way
+        [
+            [Robot, Empty, Empty, Empty, Empty],
+            [Empty, Empty, Empty, Empty, Empty],
+            [Empty, Astro, Goal, Robot, Empty],
+            [Empty, Empty, Empty, Empty, Empty],
+            [Empty, Empty, Empty, Empty, Empty],
+        ],
+        //...and can't stop if nothing is in their way
+        [
+            [Robot, Empty, Empty, Empty, Empty],
+            [Empty, Empty, Goal, Robot, Empty],
+            [Empty, Empty, Empty, Empty, Empty],
+            [Empty, Empty, Empty, Empty, Empty],
+            [Astro, Empty, Empty, Empty, Empty],
+        ],
+        //robots can be moved out of the way, or into place
+        [
+            [Empty, Empty, Empty, Empty, Empty],
+            [Empty, Empty, Empty, Robot, Empty],
+            [Empty, Astro, Empty, Empty, Empty],
+            [Empty, Empty, Goal, Empty, Empty],
+            [Empty, Empty, Robot, Empty, Robot],
+        ],
+        [
+            [Empty, Empty, Empty, Empty, Empty],
+            [Robot, Empty, Robot, Empty, Astro],
+            [Empty, Empty, Empty, Goal, Empty],
+            [Empty, Empty, Empty, Empty, Empty],
+            [Empty, Empty, Empty, Robot, Empty],
+        ],
+        [
+            [Robot, Empty, Robot, Empty, Robot],
+            [Empty, Empty, Empty, Empty, Empty],
+            [Empty, Empty, Goal, Empty, Empty],
+            [Empty, Empty, Empty, Empty, Robot],
+            [Empty, Astro, Empty, Empty, Empty],
+        ],
+    ]
+};
+
+pub fn boards() -> Result<Vec<State>, GameError> {
+    BOARDS
+        .iter()
+        .map(|board| {
+            let values = board.iter().flatten().copied();
+            State::from_grid(&Grid::new(SIZE, SIZE, values.collect()))
+        })
+        .collect()
+}